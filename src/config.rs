@@ -0,0 +1,387 @@
+use crate::data::{toml, Value};
+use crate::editorconfig::{self, EditorConfig};
+use crate::glob::Glob;
+use crate::options::{FormatOptions, PartialOptions};
+use crate::schema::Schema;
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+/// Names of config files, in order of preference when a directory has both
+const FILE_NAMES: [&str; 2] = ["mdf.toml", ".mdf.toml"];
+
+/// Project settings read from an `mdf.toml` file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// Directory the config applies to, which override globs are relative to
+    pub dir: PathBuf,
+    /// Stop looking for configs in parent directories
+    pub root: bool,
+    /// Formatter options, keyed by their command line names
+    pub options: PartialOptions,
+    /// Checks applied to every document's front matter
+    pub schema: Option<Schema>,
+    /// `[[overrides]]` sections, applied in order over `options`
+    pub overrides: Vec<Override>,
+}
+
+/// Formatter options for the files matching any of a set of globs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Override {
+    pub files: Vec<Glob>,
+    pub options: PartialOptions,
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut config = Self::parse(&source).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let path = fs::canonicalize(path)
+            .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+        config.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(config)
+    }
+
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let Value::Table(entries) = toml::parse_lenient(source)? else {
+            return Err("expected a table of settings".into());
+        };
+
+        let mut config = Config::default();
+        for (key, value) in &entries {
+            match key.as_str() {
+                "root" => config.root = parse_bool(key, value)?,
+                "front-matter" => config.schema = Some(Schema::from_config(value)?),
+                "overrides" => config.overrides = parse_overrides(value)?,
+                key => set_option(&mut config.options, key, value)?,
+            }
+        }
+        Ok(config)
+    }
+
+    /// Overwrites the settings this config sets for `file`
+    fn apply_to(&self, settings: &mut Settings, file: &Path) {
+        self.options.apply_to(&mut settings.options);
+        if let Some(schema) = &self.schema {
+            settings.schema = schema.clone();
+        }
+        let Ok(relative) = file.strip_prefix(&self.dir) else {
+            return;
+        };
+        for section in &self.overrides {
+            if section.files.iter().any(|glob| glob.matches(relative)) {
+                section.options.apply_to(&mut settings.options);
+            }
+        }
+    }
+}
+
+fn parse_overrides(value: &Value) -> Result<Vec<Override>, String> {
+    let error = || "`overrides` must be an array of tables".to_string();
+    let Value::Array(items) = value else {
+        return Err(error());
+    };
+
+    let mut overrides = vec![];
+    for item in items {
+        let Value::Table(entries) = item else {
+            return Err(error());
+        };
+        let mut section = Override::default();
+        for (key, value) in entries {
+            match (key.as_str(), value) {
+                ("files", Value::String(pattern)) => section.files = vec![Glob::new(pattern)?],
+                ("files", value) => {
+                    section.files = parse_strings(key, value)?
+                        .iter()
+                        .map(|pattern| Glob::new(pattern))
+                        .collect::<Result<_, _>>()?
+                }
+                (key, value) => set_option(&mut section.options, key, value)?,
+            }
+        }
+        if section.files.is_empty() {
+            return Err("every override needs `files`".into());
+        }
+        overrides.push(section);
+    }
+    Ok(overrides)
+}
+
+fn set_option(options: &mut PartialOptions, key: &str, value: &Value) -> Result<(), String> {
+    match key {
+        "wrap-bare-urls" => options.wrap_bare_urls = Some(parse_bool(key, value)?),
+        "strikethrough-delimiter" => {
+            options.strikethrough_delimiter = Some(parse_enum(key, value)?)
+        }
+        "hard-break" => options.hard_break = Some(parse_enum(key, value)?),
+        "table-style" => options.table_style = Some(parse_enum(key, value)?),
+        "list-marker" => options.list_marker = Some(parse_enum(key, value)?),
+        "heading-style" => options.heading_style = Some(parse_enum(key, value)?),
+        "indent-style" => options.indent_style = Some(parse_enum(key, value)?),
+        "indent-width" => options.indent_width = Some(parse_usize(key, value)?),
+        "table-max-width" => options.table_max_width = Some(parse_usize(key, value)?),
+        "footnotes-at-end" => options.footnotes_at_end = Some(parse_bool(key, value)?),
+        "renumber-footnotes" => options.renumber_footnotes = Some(parse_bool(key, value)?),
+        "wiki-links" => options.wiki_links = Some(parse_bool(key, value)?),
+        "normalize-front-matter" => options.normalize_front_matter = Some(parse_bool(key, value)?),
+        "front-matter-key-order" => {
+            options.front_matter_key_order = Some(parse_strings(key, value)?)
+        }
+        "front-matter-quote" => options.front_matter_quote = Some(parse_enum(key, value)?),
+        "front-matter-indent" => options.front_matter_indent = Some(parse_usize(key, value)?),
+        "prose-wrap" => options.prose_wrap = Some(parse_enum(key, value)?),
+        "line-width" => options.line_width = Some(parse_usize(key, value)?),
+        "abbreviations" => options.abbreviations = Some(parse_strings(key, value)?),
+        "end-of-line" => options.end_of_line = Some(parse_enum(key, value)?),
+        "insert-final-newline" => options.insert_final_newline = Some(parse_bool(key, value)?),
+        "trim-trailing-whitespace" => {
+            options.trim_trailing_whitespace = Some(parse_bool(key, value)?)
+        }
+        "extensions" => options.extensions = Some(parse_strings(key, value)?),
+        _ => return Err(format!("unknown config key `{}`", key)),
+    }
+    Ok(())
+}
+
+fn parse_bool(key: &str, value: &Value) -> Result<bool, String> {
+    match value {
+        Value::Literal(raw) if raw == "true" => Ok(true),
+        Value::Literal(raw) if raw == "false" => Ok(false),
+        _ => Err(format!("`{}` must be true or false", key)),
+    }
+}
+
+fn parse_usize(key: &str, value: &Value) -> Result<usize, String> {
+    match value {
+        Value::Literal(raw) => raw
+            .parse()
+            .map_err(|_| format!("`{}` must be a positive integer", key)),
+        _ => Err(format!("`{}` must be a positive integer", key)),
+    }
+}
+
+/// Parses one of the values accepted by the command line option of the same
+/// name
+fn parse_enum<T: ValueEnum>(key: &str, value: &Value) -> Result<T, String> {
+    let Value::String(name) = value else {
+        return Err(format!("`{}` must be a string", key));
+    };
+    T::from_str(name, false).map_err(|_| {
+        let names = T::value_variants()
+            .iter()
+            .filter_map(|v| v.to_possible_value())
+            .map(|v| format!("`{}`", v.get_name()))
+            .collect::<Vec<_>>();
+        format!(
+            "invalid value `{}` for `{}`, expected one of {}",
+            name,
+            key,
+            names.join(", ")
+        )
+    })
+}
+
+fn parse_strings(key: &str, value: &Value) -> Result<Vec<String>, String> {
+    let error = || format!("`{}` must be an array of strings", key);
+    let Value::Array(items) = value else {
+        return Err(error());
+    };
+    items
+        .iter()
+        .map(|item| match item {
+            Value::String(text) => Ok(text.clone()),
+            _ => Err(error()),
+        })
+        .collect()
+}
+
+/// The formatter options and front matter checks that apply to a file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Settings {
+    pub options: FormatOptions,
+    pub schema: Schema,
+}
+
+/// Finds the settings for each file from the configs in its directory and
+/// the directories above it, up to one with `root = true`. Configs in nested
+/// directories override those above them, overrides matching the file
+/// override the rest of their config, and options given on the command line
+/// override everything. `.editorconfig` files are found the same way and
+/// apply below all configs.
+pub struct Resolver {
+    cli: PartialOptions,
+    /// Config given with `--config`, used instead of looking for configs
+    explicit: Option<Rc<Config>>,
+    /// Configs that apply to each directory visited so far, outermost first
+    configs: HashMap<PathBuf, Vec<Rc<Config>>>,
+    /// `.editorconfig` files that apply to each directory, outermost first
+    editorconfigs: HashMap<PathBuf, Vec<Rc<EditorConfig>>>,
+}
+
+impl Resolver {
+    pub fn new(cli: PartialOptions, explicit: Option<Config>) -> Self {
+        Self {
+            cli,
+            explicit: explicit.map(Rc::new),
+            configs: HashMap::new(),
+            editorconfigs: HashMap::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, file: &Path) -> Result<Settings, String> {
+        let file = fs::canonicalize(file)
+            .map_err(|e| format!("Failed to resolve {}: {}", file.display(), e))?;
+        let dir = file.parent().unwrap_or(Path::new("/"));
+        let configs = match &self.explicit {
+            Some(config) => vec![config.clone()],
+            None => find_files(&mut self.configs, dir, &FILE_NAMES, Config::load, |c| {
+                c.root
+            })?,
+        };
+        let editorconfigs = find_files(
+            &mut self.editorconfigs,
+            dir,
+            &[editorconfig::FILE_NAME],
+            EditorConfig::load,
+            |e| e.root,
+        )?;
+
+        let mut editor_options = PartialOptions::default();
+        for editorconfig in editorconfigs {
+            editorconfig.apply_to(&mut editor_options, &file);
+        }
+        let mut settings = Settings::default();
+        editor_options.apply_to(&mut settings.options);
+        for config in configs {
+            config.apply_to(&mut settings, &file);
+        }
+        self.cli.apply_to(&mut settings.options);
+        Ok(settings)
+    }
+}
+
+/// Returns the files that apply to `dir`, outermost first: the first of
+/// `names` found in `dir` and in each directory above it, up to a file that
+/// `is_root` accepts
+fn find_files<T>(
+    cache: &mut HashMap<PathBuf, Vec<Rc<T>>>,
+    dir: &Path,
+    names: &[&str],
+    load: fn(&Path) -> Result<T, String>,
+    is_root: fn(&T) -> bool,
+) -> Result<Vec<Rc<T>>, String> {
+    if let Some(files) = cache.get(dir) {
+        return Ok(files.clone());
+    }
+
+    let file = names
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .map(|path| load(&path))
+        .transpose()?;
+    let mut files = match (&file, dir.parent()) {
+        (Some(file), _) if is_root(file) => vec![],
+        (_, Some(parent)) => find_files(cache, parent, names, load, is_root)?,
+        (_, None) => vec![],
+    };
+    files.extend(file.map(Rc::new));
+    cache.insert(dir.to_path_buf(), files.clone());
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{EndOfLine, HeadingStyle, ListMarker, ProseWrap};
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse("[front-matter]\nrequired = [\"title\"]\n").unwrap();
+        assert_eq!(config.schema.unwrap().required, vec!["title".to_string()]);
+
+        let config = Config::parse(
+            "root = true\nprose-wrap = \"always\"\nline-width = 72\nabbreviations = [\"etc.\"]\n",
+        )
+        .unwrap();
+        assert!(config.root);
+        assert_eq!(config.options.prose_wrap, Some(ProseWrap::Always));
+        assert_eq!(config.options.line_width, Some(72));
+        assert_eq!(config.options.abbreviations, Some(vec!["etc.".to_string()]));
+
+        let config = Config::parse(
+            "# Shared settings\nline-width = 72 # columns\n\n\
+             [front-matter] # checks\ntypes.title = \"string\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.options.line_width, Some(72));
+        assert!(config.schema.is_some());
+
+        let config = Config::parse("list-marker = \"dash\"\nheading-style = \"setext\"").unwrap();
+        assert_eq!(config.options.list_marker, Some(ListMarker::Dash));
+        assert_eq!(config.options.heading_style, Some(HeadingStyle::Setext));
+
+        let config = Config::parse("extensions = [\"md\", \"txt\"]").unwrap();
+        assert_eq!(
+            config.options.extensions,
+            Some(vec!["md".to_string(), "txt".to_string()])
+        );
+
+        assert!(Config::parse("unknown = 1").is_err());
+        assert!(Config::parse("prose-wrap = \"sometimes\"").is_err());
+        assert!(Config::parse("line-width = \"72\"").is_err());
+        assert!(Config::parse("[[overrides]]\nline-width = 72").is_err());
+        assert!(Config::parse("[[overrides]]\nfiles = \"a\"\nroot = true").is_err());
+    }
+
+    #[test]
+    fn test_resolve_nested_configs() {
+        let root = std::env::temp_dir().join(format!("mdf-config-{}", std::process::id()));
+        let write = |path: &str, content: &str| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write(
+            "mdf.toml",
+            "root = true\nline-width = 100\nprose-wrap = \"always\"\n",
+        );
+        write(
+            "docs/.mdf.toml",
+            "line-width = 60\n\n[[overrides]]\nfiles = [\"api/**\"]\nline-width = 120\n",
+        );
+        write("blog/mdf.toml", "root = true\nwiki-links = true\n");
+        write(
+            ".editorconfig",
+            "[*.md]\nmax_line_length = 90\nend_of_line = crlf\n",
+        );
+        write("docs/a.md", "");
+        write("blog/b.md", "");
+        write("docs/api/c.md", "");
+
+        let cli = PartialOptions {
+            wiki_links: Some(false),
+            ..Default::default()
+        };
+        let mut resolver = Resolver::new(cli, None);
+        let docs = resolver.resolve(&root.join("docs/a.md")).unwrap().options;
+        let blog = resolver.resolve(&root.join("blog/b.md")).unwrap().options;
+        let api = resolver
+            .resolve(&root.join("docs/api/c.md"))
+            .unwrap()
+            .options;
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(docs.line_width, 60);
+        assert_eq!(docs.prose_wrap, ProseWrap::Always);
+        assert_eq!(docs.end_of_line, EndOfLine::Crlf);
+        assert_eq!(blog.line_width, 90);
+        assert_eq!(blog.prose_wrap, ProseWrap::default());
+        assert!(!blog.wiki_links);
+        assert_eq!(api.line_width, 120);
+    }
+}
@@ -0,0 +1,202 @@
+pub mod json;
+pub mod toml;
+pub mod yaml;
+
+/// A structured value read from front matter or a config file
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    /// A non-string scalar (number, boolean, date or null), kept as written
+    Literal(String),
+    Array(Vec<Value>),
+    /// Key-value pairs in source order
+    Table(Vec<(String, Value)>),
+}
+
+/// The type of a `Value`, as far as front matter checks are concerned
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    String,
+    Number,
+    Bool,
+    Date,
+    Null,
+    Array,
+    Table,
+}
+
+impl Value {
+    pub fn kind(&self) -> Kind {
+        match self {
+            Value::String(_) => Kind::String,
+            Value::Literal(raw) if is_bool(raw) => Kind::Bool,
+            Value::Literal(raw) if is_number(raw) => Kind::Number,
+            Value::Literal(raw) if is_date(raw) => Kind::Date,
+            Value::Literal(_) => Kind::Null,
+            Value::Array(_) => Kind::Array,
+            Value::Table(_) => Kind::Table,
+        }
+    }
+
+    /// Looks up `key` in a table
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Table(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+}
+
+/// Reorders the entries of a table so that keys in `priority` come first, in
+/// that order, followed by the remaining keys in their original order
+pub fn sort_keys(entries: &mut [(String, Value)], priority: &[String]) {
+    entries.sort_by_key(|(key, _)| {
+        priority
+            .iter()
+            .position(|p| p == key)
+            .unwrap_or(priority.len())
+    });
+}
+
+fn is_bool(raw: &str) -> bool {
+    matches!(
+        raw.to_ascii_lowercase().as_str(),
+        "true" | "false" | "yes" | "no" | "on" | "off"
+    )
+}
+
+fn is_number(raw: &str) -> bool {
+    let text = raw.trim_start_matches(['+', '-']).replace('_', "");
+    if matches!(
+        text.to_ascii_lowercase().as_str(),
+        "inf" | ".inf" | "nan" | ".nan"
+    ) {
+        return true;
+    }
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0o")) {
+        return !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    text.parse::<f64>().is_ok() && text.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+}
+
+pub fn is_date(raw: &str) -> bool {
+    let bytes = raw.as_bytes();
+    bytes.len() >= 10
+        && bytes[..10].iter().enumerate().all(|(i, b)| {
+            if i == 4 || i == 7 {
+                *b == b'-'
+            } else {
+                b.is_ascii_digit()
+            }
+        })
+}
+
+/// Splits `text` on `separator` where it is not inside quotes or brackets
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut escaped = false;
+    let mut start = 0;
+    for (idx, ch) in text.char_indices() {
+        match (quote, ch) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(q), ch) if ch == q && !escaped => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(ch),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth = depth.saturating_sub(1),
+            (None, ch) if ch == separator && depth == 0 => {
+                parts.push(&text[start..idx]);
+                start = idx + ch.len_utf8();
+            }
+            _ => {}
+        }
+        escaped = false;
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+/// Parses a double-quoted string with backslash escapes, which must make up
+/// all of `text`
+fn parse_double_quoted(text: &str) -> Result<String, String> {
+    let inner = text
+        .strip_prefix('"')
+        .ok_or_else(|| format!("expected a string: {}", text))?;
+    let mut value = String::new();
+    let mut chars = inner.char_indices();
+    while let Some((idx, ch)) = chars.next() {
+        match ch {
+            '"' if idx + 1 == inner.len() => return Ok(value),
+            '"' => return Err(format!("unexpected text after string: {}", text)),
+            '\\' => match chars.next().map(|(_, c)| c) {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('r') => value.push('\r'),
+                Some('0') => value.push('\0'),
+                Some('b') => value.push('\u{8}'),
+                Some('f') => value.push('\u{c}'),
+                Some('u') => {
+                    let hex = chars.by_ref().take(4).map(|(_, c)| c).collect::<String>();
+                    let ch = u32::from_str_radix(&hex, 16)
+                        .ok()
+                        .and_then(char::from_u32)
+                        .ok_or_else(|| format!("invalid escape in {}", text))?;
+                    value.push(ch);
+                }
+                Some(ch @ ('"' | '\\' | '/')) => value.push(ch),
+                _ => return Err(format!("invalid escape in {}", text)),
+            },
+            ch => value.push(ch),
+        }
+    }
+    Err(format!("unterminated string: {}", text))
+}
+
+/// Writes a double-quoted string with backslash escapes
+fn double_quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            ch if ch.is_control() => quoted.push_str(&format!("\\u{:04X}", ch as u32)),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind() {
+        assert_eq!(Value::Literal("-1.5e3".into()).kind(), Kind::Number);
+        assert_eq!(Value::Literal("True".into()).kind(), Kind::Bool);
+        assert_eq!(Value::Literal("2024-01-31".into()).kind(), Kind::Date);
+        assert_eq!(Value::Literal("null".into()).kind(), Kind::Null);
+        assert_eq!(Value::String("12".into()).kind(), Kind::String);
+    }
+
+    #[test]
+    fn test_sort_keys() {
+        let mut entries = ["tags", "date", "extra", "title"]
+            .iter()
+            .map(|key| (key.to_string(), Value::Literal("1".into())))
+            .collect::<Vec<_>>();
+        sort_keys(&mut entries, &["title".into(), "date".into()]);
+
+        let keys = entries.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>();
+        assert_eq!(keys, ["title", "date", "tags", "extra"]);
+    }
+}
@@ -0,0 +1,181 @@
+//! JSON front matter, read so that it can be checked like YAML and TOML.
+//! It is never written back, so the whole of JSON is accepted.
+
+use super::{is_number, parse_double_quoted, Value};
+
+/// Parses a JSON document, which must hold an object
+pub fn parse(source: &str) -> Result<Value, String> {
+    let mut cursor = Cursor { source, pos: 0 };
+    cursor.skip_whitespace();
+    let value = cursor.parse_value()?;
+    cursor.skip_whitespace();
+    if cursor.peek().is_some() {
+        return Err(format!("unexpected text: {}", cursor.line()));
+    }
+    match value {
+        Value::Table(_) => Ok(value),
+        _ => Err("expected an object".into()),
+    }
+}
+
+struct Cursor<'s> {
+    source: &'s str,
+    pos: usize,
+}
+
+impl<'s> Cursor<'s> {
+    fn rest(&self) -> &'s str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// The rest of the current line, for error messages
+    fn line(&self) -> &'s str {
+        self.rest().lines().next().unwrap_or_default()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), String> {
+        if self.peek() == Some(ch) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{}`: {}", ch, self.line()))
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        match self.peek() {
+            Some('{') => {
+                self.pos += 1;
+                let mut entries: Vec<(String, Value)> = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some('}') {
+                    self.pos += 1;
+                    return Ok(Value::Table(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.parse_string()?;
+                    self.skip_whitespace();
+                    self.expect(':')?;
+                    self.skip_whitespace();
+                    let value = self.parse_value()?;
+                    if entries.iter().any(|(k, _)| *k == key) {
+                        return Err(format!("duplicate key: {}", key));
+                    }
+                    entries.push((key, value));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some('}') => {
+                            self.pos += 1;
+                            return Ok(Value::Table(entries));
+                        }
+                        _ => return Err(format!("expected `,` or `}}`: {}", self.line())),
+                    }
+                }
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                self.skip_whitespace();
+                if self.peek() == Some(']') {
+                    self.pos += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    self.skip_whitespace();
+                    items.push(self.parse_value()?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(',') => self.pos += 1,
+                        Some(']') => {
+                            self.pos += 1;
+                            return Ok(Value::Array(items));
+                        }
+                        _ => return Err(format!("expected `,` or `]`: {}", self.line())),
+                    }
+                }
+            }
+            Some('"') => self.parse_string().map(Value::String),
+            _ => {
+                let rest = self.rest();
+                let end = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')))
+                    .unwrap_or(rest.len());
+                let literal = &rest[..end];
+                if !matches!(literal, "true" | "false" | "null") && !is_number(literal) {
+                    return Err(format!("invalid value: {}", self.line()));
+                }
+                self.pos += end;
+                Ok(Value::Literal(literal.into()))
+            }
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        if !rest.starts_with('"') {
+            return Err(format!("expected a string: {}", self.line()));
+        }
+        let mut escaped = false;
+        for (idx, ch) in rest.char_indices().skip(1) {
+            match ch {
+                '\n' => break,
+                '"' if !escaped => {
+                    self.pos += idx + 1;
+                    return parse_double_quoted(&rest[..=idx]);
+                }
+                '\\' => escaped = !escaped,
+                _ => escaped = false,
+            }
+        }
+        Err(format!("unterminated string: {}", self.line()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source =
+            "{\n  \"title\": \"A \\\"quote\\\" \\u00e9\",\n  \"tags\": [\"a\", 1.5e3],\n  \
+                      \"extra\": {\"draft\": false, \"n\": null}\n}\n";
+
+        assert_eq!(
+            parse(source),
+            Ok(Value::Table(vec![
+                ("title".into(), Value::String("A \"quote\" é".into())),
+                (
+                    "tags".into(),
+                    Value::Array(vec![
+                        Value::String("a".into()),
+                        Value::Literal("1.5e3".into())
+                    ])
+                ),
+                (
+                    "extra".into(),
+                    Value::Table(vec![
+                        ("draft".into(), Value::Literal("false".into())),
+                        ("n".into(), Value::Literal("null".into())),
+                    ])
+                ),
+            ]))
+        );
+        assert_eq!(parse("{}"), Ok(Value::Table(vec![])));
+        assert!(parse("[1]").is_err());
+        assert!(parse("{\"a\": 1,}").is_err());
+        assert!(parse("{\"a\": yes}").is_err());
+        assert!(parse("{\"a\": 1} x").is_err());
+    }
+}
@@ -0,0 +1,593 @@
+//! The subset of TOML used in front matter and config files: key-value
+//! pairs, `[table]` and `[[array]]` headers, basic and literal strings,
+//! arrays and inline tables. Multi-line strings are rejected. Front matter
+//! also rejects comments and dotted keys so that nothing is lost when it is
+//! written back, while config files, which are only read, may use them.
+
+use super::{double_quote, parse_double_quoted, Kind, Value};
+use crate::options::QuoteStyle;
+
+/// Parses a TOML document into a table
+pub fn parse(source: &str) -> Result<Value, String> {
+    parse_document(source, false)
+}
+
+/// Parses a TOML document into a table, skipping comments and expanding
+/// dotted keys into tables
+pub fn parse_lenient(source: &str) -> Result<Value, String> {
+    parse_document(source, true)
+}
+
+fn parse_document(source: &str, lenient: bool) -> Result<Value, String> {
+    let mut cursor = Cursor {
+        source,
+        pos: 0,
+        lenient,
+    };
+    let mut root = vec![];
+    let mut path: Vec<String> = vec![];
+    let mut headers: Vec<Vec<String>> = vec![];
+    // Paths declared with `[[array]]` headers
+    let mut arrays: Vec<Vec<String>> = vec![];
+
+    loop {
+        cursor.skip_blank()?;
+        match cursor.peek() {
+            None => break,
+            Some('[') if cursor.rest().starts_with("[[") => {
+                cursor.pos += 2;
+                path = cursor.parse_key_path(']')?;
+                cursor.expect(']')?;
+                cursor.expect(']')?;
+                let (key, parent) = path.split_last().unwrap();
+                let table = table_at(&mut root, parent)?;
+                match table.iter_mut().find(|(k, _)| k == key) {
+                    Some((_, Value::Array(items))) if arrays.contains(&path) => {
+                        items.push(Value::Table(vec![]))
+                    }
+                    Some(_) => return Err(format!("`{}` is already defined", key)),
+                    None => {
+                        table.push((key.clone(), Value::Array(vec![Value::Table(vec![])])));
+                        arrays.push(path.clone());
+                    }
+                }
+                // Sub-tables of the previous element may be declared again
+                headers.retain(|header| !header.starts_with(&path));
+            }
+            Some('[') => {
+                cursor.pos += 1;
+                path = cursor.parse_key_path(']')?;
+                cursor.expect(']')?;
+                if headers.contains(&path) {
+                    return Err(format!("duplicate table: {}", path.join(".")));
+                }
+                table_at(&mut root, &path)?;
+                headers.push(path.clone());
+            }
+            Some(_) => {
+                let (key, value) = cursor.parse_entry()?;
+                let table = table_at(&mut root, &path)?;
+                insert(table, key, value)?;
+            }
+        }
+        cursor.skip_whitespace(false);
+        cursor.skip_comment()?;
+        match cursor.peek() {
+            None | Some('\n') | Some('\r') => {}
+            Some(_) => return Err(format!("unexpected text: {}", cursor.line())),
+        }
+    }
+
+    Ok(Value::Table(root))
+}
+
+/// Adds an entry to a table. Dotted keys add to the tables they name.
+fn insert(table: &mut Vec<(String, Value)>, key: Vec<String>, value: Value) -> Result<(), String> {
+    let (key, parents) = key.split_last().unwrap();
+    let table = table_at(table, parents)?;
+    if table.iter().any(|(k, _)| k == key) {
+        return Err(format!("duplicate key: {}", key));
+    }
+    table.push((key.clone(), value));
+    Ok(())
+}
+
+/// Returns the table at `path`, creating missing tables along the way
+fn table_at<'v>(
+    root: &'v mut Vec<(String, Value)>,
+    path: &[String],
+) -> Result<&'v mut Vec<(String, Value)>, String> {
+    let mut table = root;
+    for key in path {
+        let idx = match table.iter().position(|(k, _)| k == key) {
+            Some(idx) => idx,
+            None => {
+                table.push((key.clone(), Value::Table(vec![])));
+                table.len() - 1
+            }
+        };
+        table = match &mut table[idx].1 {
+            Value::Table(child) => child,
+            // An array of tables continues with its last element
+            Value::Array(items) => match items.last_mut() {
+                Some(Value::Table(child)) => child,
+                _ => return Err(format!("`{}` is not a table", key)),
+            },
+            _ => return Err(format!("`{}` is not a table", key)),
+        };
+    }
+    Ok(table)
+}
+
+struct Cursor<'s> {
+    source: &'s str,
+    pos: usize,
+    /// Accept comments and dotted keys
+    lenient: bool,
+}
+
+impl<'s> Cursor<'s> {
+    fn rest(&self) -> &'s str {
+        &self.source[self.pos..]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// The rest of the current line, for error messages
+    fn line(&self) -> &'s str {
+        self.rest().lines().next().unwrap_or_default()
+    }
+
+    fn skip_whitespace(&mut self, newlines: bool) {
+        let rest = self.rest();
+        let trimmed = rest.trim_start_matches(|c| {
+            c == ' ' || c == '\t' || (newlines && (c == '\n' || c == '\r'))
+        });
+        self.pos += rest.len() - trimmed.len();
+    }
+
+    /// Skips a comment up to the end of the line
+    fn skip_comment(&mut self) -> Result<(), String> {
+        if self.peek() != Some('#') {
+            return Ok(());
+        }
+        if !self.lenient {
+            return Err("comments are not supported".into());
+        }
+        self.pos += self.line().len();
+        Ok(())
+    }
+
+    /// Skips whitespace, newlines and comments
+    fn skip_blank(&mut self) -> Result<(), String> {
+        loop {
+            self.skip_whitespace(true);
+            if self.peek() != Some('#') {
+                return Ok(());
+            }
+            self.skip_comment()?;
+        }
+    }
+
+    /// Parses a `key = value` pair, whose key is a path when dotted
+    fn parse_entry(&mut self) -> Result<(Vec<String>, Value), String> {
+        let key = if self.lenient {
+            self.parse_key_path('=')?
+        } else {
+            vec![self.parse_key()?]
+        };
+        self.skip_whitespace(false);
+        self.expect('=')?;
+        self.skip_whitespace(false);
+        Ok((key, self.parse_value()?))
+    }
+
+    fn expect(&mut self, ch: char) -> Result<(), String> {
+        if self.peek() == Some(ch) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(format!("expected `{}`: {}", ch, self.line()))
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String, String> {
+        let key = match self.peek() {
+            Some('"' | '\'') => match self.parse_value()? {
+                Value::String(key) => key,
+                _ => unreachable!(),
+            },
+            _ => {
+                let rest = self.rest();
+                let end = rest
+                    .find(|c: char| !is_bare_key_char(c))
+                    .unwrap_or(rest.len());
+                if end == 0 {
+                    return Err(format!("expected a key: {}", self.line()));
+                }
+                self.pos += end;
+                rest[..end].to_string()
+            }
+        };
+        if self.rest().trim_start().starts_with('.') {
+            return Err(format!("dotted keys are not supported: {}", key));
+        }
+        Ok(key)
+    }
+
+    /// Parses a dotted table name up to `end`
+    fn parse_key_path(&mut self, end: char) -> Result<Vec<String>, String> {
+        let mut path = vec![];
+        loop {
+            self.skip_whitespace(false);
+            let key = match self.peek() {
+                Some('"' | '\'') => match self.parse_value()? {
+                    Value::String(key) => key,
+                    _ => unreachable!(),
+                },
+                _ => {
+                    let rest = self.rest();
+                    let len = rest
+                        .find(|c: char| !is_bare_key_char(c))
+                        .unwrap_or(rest.len());
+                    self.pos += len;
+                    rest[..len].to_string()
+                }
+            };
+            if key.is_empty() {
+                return Err(format!("expected a table name: {}", self.line()));
+            }
+            path.push(key);
+            self.skip_whitespace(false);
+            match self.peek() {
+                Some('.') => self.pos += 1,
+                Some(ch) if ch == end => return Ok(path),
+                _ => return Err(format!("invalid table name: {}", self.line())),
+            }
+        }
+    }
+
+    fn parse_value(&mut self) -> Result<Value, String> {
+        let rest = self.rest();
+        if rest.starts_with("\"\"\"") || rest.starts_with("'''") {
+            return Err("multi-line strings are not supported".into());
+        }
+
+        match self.peek() {
+            Some('"') => {
+                let end = string_end(rest, '"')
+                    .ok_or_else(|| format!("unterminated string: {}", self.line()))?;
+                self.pos += end;
+                parse_double_quoted(&rest[..end]).map(Value::String)
+            }
+            Some('\'') => {
+                let end = 1 + rest[1..]
+                    .find(['\'', '\n'])
+                    .filter(|&idx| rest[1 + idx..].starts_with('\''))
+                    .ok_or_else(|| format!("unterminated string: {}", self.line()))?;
+                self.pos += end + 1;
+                Ok(Value::String(rest[1..end].into()))
+            }
+            Some('[') => {
+                self.pos += 1;
+                let mut items = vec![];
+                loop {
+                    self.skip_blank()?;
+                    if self.peek() == Some(']') {
+                        self.pos += 1;
+                        return Ok(Value::Array(items));
+                    }
+                    items.push(self.parse_value()?);
+                    self.skip_blank()?;
+                    if self.peek() == Some(',') {
+                        self.pos += 1;
+                    } else if self.peek() != Some(']') {
+                        return Err(format!("expected `,` or `]`: {}", self.line()));
+                    }
+                }
+            }
+            Some('{') => {
+                self.pos += 1;
+                let mut entries: Vec<(String, Value)> = vec![];
+                loop {
+                    self.skip_whitespace(false);
+                    if self.peek() == Some('}') {
+                        self.pos += 1;
+                        return Ok(Value::Table(entries));
+                    }
+                    let (key, value) = self.parse_entry()?;
+                    insert(&mut entries, key, value)?;
+                    self.skip_whitespace(false);
+                    if self.peek() == Some(',') {
+                        self.pos += 1;
+                    } else if self.peek() != Some('}') {
+                        return Err(format!("expected `,` or `}}`: {}", self.line()));
+                    }
+                }
+            }
+            _ => {
+                let end = rest
+                    .find([',', ']', '}', '\n', '\r', '#'])
+                    .unwrap_or(rest.len());
+                let literal = rest[..end].trim_end();
+                let value = Value::Literal(literal.into());
+                if !matches!(value.kind(), Kind::Bool | Kind::Number | Kind::Date) {
+                    return Err(format!("invalid value: {}", self.line()));
+                }
+                self.pos += literal.len();
+                Ok(value)
+            }
+        }
+    }
+}
+
+fn is_bare_key_char(ch: char) -> bool {
+    ch.is_ascii_alphanumeric() || ch == '_' || ch == '-'
+}
+
+/// Returns the length of the basic string starting `text`, including quotes
+fn string_end(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (idx, ch) in text.char_indices().skip(1) {
+        match ch {
+            '\n' => return None,
+            '\\' if !escaped => {
+                escaped = true;
+                continue;
+            }
+            ch if ch == quote && !escaped => return Some(idx + 1),
+            _ => {}
+        }
+        escaped = false;
+    }
+    None
+}
+
+/// Writes a table as a TOML document. Keys with table values become
+/// `[table]` sections after the plain keys, and arrays of tables become
+/// `[[array]]` sections.
+pub fn write(value: &Value, quote: QuoteStyle) -> String {
+    let mut out = String::new();
+    if let Value::Table(entries) = value {
+        write_table(entries, &[], quote, &mut out);
+    }
+    out
+}
+
+/// Checks for a non-empty array holding only tables, which is written as
+/// `[[array]]` sections
+fn is_array_of_tables(value: &Value) -> bool {
+    matches!(value, Value::Array(items)
+        if !items.is_empty() && items.iter().all(|item| matches!(item, Value::Table(_))))
+}
+
+fn write_table(entries: &[(String, Value)], path: &[String], quote: QuoteStyle, out: &mut String) {
+    for (key, value) in entries {
+        if !matches!(value, Value::Table(_)) && !is_array_of_tables(value) {
+            out.push_str(&format!(
+                "{} = {}\n",
+                write_key(key, quote),
+                write_value(value, quote)
+            ));
+        }
+    }
+    for (key, value) in entries {
+        let mut path = path.to_vec();
+        path.push(write_key(key, quote));
+        match value {
+            Value::Table(entries) => {
+                if !out.is_empty() {
+                    out.push('\n');
+                }
+                out.push_str(&format!("[{}]\n", path.join(".")));
+                write_table(entries, &path, quote, out);
+            }
+            Value::Array(items) if is_array_of_tables(value) => {
+                for item in items {
+                    if let Value::Table(entries) = item {
+                        if !out.is_empty() {
+                            out.push('\n');
+                        }
+                        out.push_str(&format!("[[{}]]\n", path.join(".")));
+                        write_table(entries, &path, quote, out);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn write_key(key: &str, quote: QuoteStyle) -> String {
+    if !key.is_empty() && key.chars().all(is_bare_key_char) {
+        key.into()
+    } else {
+        write_string(key, quote)
+    }
+}
+
+fn write_value(value: &Value, quote: QuoteStyle) -> String {
+    match value {
+        Value::String(text) => write_string(text, quote),
+        Value::Literal(raw) => raw.clone(),
+        Value::Array(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(|item| write_value(item, quote))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Table(entries) if entries.is_empty() => "{}".into(),
+        Value::Table(entries) => format!(
+            "{{ {} }}",
+            entries
+                .iter()
+                .map(|(k, v)| format!("{} = {}", write_key(k, quote), write_value(v, quote)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+fn write_string(text: &str, quote: QuoteStyle) -> String {
+    if quote == QuoteStyle::Single && !text.contains(|c: char| c == '\'' || c.is_control()) {
+        format!("'{}'", text)
+    } else {
+        double_quote(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "\
+title = 'Hello \"world\"'
+date = 2024-01-31T10:00:00Z
+tags = [
+  \"rust\",
+  \"cli\",
+]
+
+[params]
+draft = false
+author = { name = \"Jane\", id = 7 }
+";
+        let string = |text: &str| Value::String(text.into());
+        let literal = |raw: &str| Value::Literal(raw.into());
+
+        assert_eq!(
+            parse(source),
+            Ok(Value::Table(vec![
+                ("title".into(), string("Hello \"world\"")),
+                ("date".into(), literal("2024-01-31T10:00:00Z")),
+                (
+                    "tags".into(),
+                    Value::Array(vec![string("rust"), string("cli")])
+                ),
+                (
+                    "params".into(),
+                    Value::Table(vec![
+                        ("draft".into(), literal("false")),
+                        (
+                            "author".into(),
+                            Value::Table(vec![
+                                ("name".into(), string("Jane")),
+                                ("id".into(), literal("7")),
+                            ])
+                        ),
+                    ])
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_array_of_tables() {
+        let source = "[[items]]\nname = 'a'\n\n[items.extra]\nx = 1\n\n[[items]]\nname = 'b'\n";
+        let table = |entries: Vec<(&str, Value)>| {
+            Value::Table(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+        };
+
+        assert_eq!(
+            parse(source),
+            Ok(table(vec![(
+                "items",
+                Value::Array(vec![
+                    table(vec![
+                        ("name", Value::String("a".into())),
+                        ("extra", table(vec![("x", Value::Literal("1".into()))])),
+                    ]),
+                    table(vec![("name", Value::String("b".into()))]),
+                ])
+            )]))
+        );
+        assert!(parse("items = []\n[[items]]").is_err());
+        assert!(parse("[items]\n[[items]]").is_err());
+    }
+
+    #[test]
+    fn test_parse_rejects_lossy_input() {
+        assert!(parse("a = 1 # note").is_err());
+        assert!(parse("a = \"\"\"\ntext\"\"\"").is_err());
+        assert!(parse("a.b = 1").is_err());
+        assert!(parse("a = bare").is_err());
+        assert!(parse("a = 1\na = 2").is_err());
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let source = "\
+# Settings
+a.b = 1 # note
+a.c = { d.e = 'x' }
+list = [
+  # first
+  1, # one
+]
+
+[t] # section
+'#' = \"# kept\"
+";
+        let table = |entries: Vec<(&str, Value)>| {
+            Value::Table(entries.into_iter().map(|(k, v)| (k.into(), v)).collect())
+        };
+
+        assert_eq!(
+            parse_lenient(source),
+            Ok(table(vec![
+                (
+                    "a",
+                    table(vec![
+                        ("b", Value::Literal("1".into())),
+                        (
+                            "c",
+                            table(vec![("d", table(vec![("e", Value::String("x".into()))]))])
+                        ),
+                    ])
+                ),
+                ("list", Value::Array(vec![Value::Literal("1".into())])),
+                ("t", table(vec![("#", Value::String("# kept".into()))])),
+            ]))
+        );
+        assert!(parse(source).is_err());
+        assert!(parse_lenient("a = 1\na.b = 2").is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let source = "title = \"T\"\n\n[extra]\nx = 1\n\n[extra.deep]\ny = 'z'\n";
+        let mut value = parse(source).unwrap();
+        if let Value::Table(entries) = &mut value {
+            entries.reverse();
+        }
+
+        // Plain keys stay ahead of sections whatever the key order
+        assert_eq!(
+            write(&value, QuoteStyle::Minimal),
+            "title = \"T\"\n\n[extra]\nx = 1\n\n[extra.deep]\ny = \"z\"\n"
+        );
+        assert_eq!(parse(&write(&value, QuoteStyle::Single)), parse(source));
+    }
+
+    #[test]
+    fn test_write_array_of_tables() {
+        let source = "tags = [\"a\"]\n\n[[items]]\nname = \"a\"\n\n[items.extra]\nx = 1\n\n\
+                      [[items]]\nname = \"b\"\n\n[[items]]\n";
+        let value = parse(source).unwrap();
+
+        assert_eq!(write(&value, QuoteStyle::Minimal), source);
+        assert_eq!(
+            write(
+                &parse("points = [{ x = 1 }, 2]").unwrap(),
+                QuoteStyle::Minimal
+            ),
+            "points = [{ x = 1 }, 2]\n"
+        );
+    }
+}
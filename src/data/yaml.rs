@@ -0,0 +1,379 @@
+//! The subset of YAML used in front matter: block mappings and sequences,
+//! flow sequences of scalars, and plain or quoted scalars. Anything that
+//! could not be written back without losing information, such as comments,
+//! anchors or block scalars, is rejected.
+
+use super::{double_quote, parse_double_quoted, split_top_level, Kind, Value};
+use crate::options::QuoteStyle;
+
+struct Line<'s> {
+    indent: usize,
+    text: &'s str,
+}
+
+/// Parses a YAML document into a table
+pub fn parse(source: &str) -> Result<Value, String> {
+    let mut lines = vec![];
+    for line in source.lines() {
+        let text = line.trim_start_matches(' ');
+        if text.trim().is_empty() {
+            continue;
+        }
+        if text.starts_with('\t') {
+            return Err("tabs are not allowed in indentation".into());
+        }
+        if text.starts_with('#') {
+            return Err("comments are not supported".into());
+        }
+        if matches!(text.trim_end(), "---" | "...") {
+            return Err("multiple documents are not supported".into());
+        }
+        lines.push(Line {
+            indent: line.len() - text.len(),
+            text: text.trim_end(),
+        });
+    }
+
+    if lines.is_empty() {
+        return Ok(Value::Table(vec![]));
+    }
+    let mut pos = 0;
+    let value = parse_mapping(&mut lines, &mut pos, 0)?;
+    if pos < lines.len() {
+        return Err(format!("unexpected line: {}", lines[pos].text));
+    }
+    Ok(value)
+}
+
+fn is_sequence_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+fn parse_node(lines: &mut [Line], pos: &mut usize) -> Result<Value, String> {
+    let indent = lines[*pos].indent;
+    if is_sequence_item(lines[*pos].text) {
+        parse_sequence(lines, pos, indent)
+    } else {
+        parse_mapping(lines, pos, indent)
+    }
+}
+
+fn parse_mapping(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, String> {
+    let mut entries: Vec<(String, Value)> = vec![];
+    while *pos < lines.len() && lines[*pos].indent == indent {
+        let text = lines[*pos].text;
+        let (key, rest) = split_key(text)?;
+        if entries.iter().any(|(k, _)| *k == key) {
+            return Err(format!("duplicate key: {}", key));
+        }
+        *pos += 1;
+
+        let value = if !rest.is_empty() {
+            parse_scalar(rest)?
+        } else {
+            match lines.get(*pos) {
+                Some(next)
+                    if next.indent > indent
+                        || (next.indent == indent && is_sequence_item(next.text)) =>
+                {
+                    parse_node(lines, pos)?
+                }
+                _ => Value::Literal("null".into()),
+            }
+        };
+        entries.push((key, value));
+    }
+
+    if *pos < lines.len() && lines[*pos].indent > indent {
+        return Err(format!("unexpected indentation: {}", lines[*pos].text));
+    }
+    Ok(Value::Table(entries))
+}
+
+fn parse_sequence(lines: &mut [Line], pos: &mut usize, indent: usize) -> Result<Value, String> {
+    let mut items = vec![];
+    while *pos < lines.len() && lines[*pos].indent == indent && is_sequence_item(lines[*pos].text) {
+        let text = lines[*pos].text;
+        let rest = text[1..].trim_start();
+
+        if rest.is_empty() {
+            *pos += 1;
+            match lines.get(*pos) {
+                Some(next) if next.indent > indent => items.push(parse_node(lines, pos)?),
+                _ => items.push(Value::Literal("null".into())),
+            }
+        } else if is_sequence_item(rest) || split_key(rest).is_ok() {
+            // The item's content continues on this line, so treat it as a line
+            // of its own starting at the item's column
+            lines[*pos] = Line {
+                indent: indent + text.len() - rest.len(),
+                text: rest,
+            };
+            items.push(parse_node(lines, pos)?);
+        } else {
+            items.push(parse_scalar(rest)?);
+            *pos += 1;
+        }
+    }
+    Ok(Value::Array(items))
+}
+
+/// Splits a `key: value` line into its key and the (possibly empty) value
+fn split_key(text: &str) -> Result<(String, &str), String> {
+    let (key, rest) = if text.starts_with(['"', '\'']) {
+        let quote = text.chars().next().unwrap();
+        let end = 1 + text[1..]
+            .find(quote)
+            .ok_or_else(|| format!("unterminated key: {}", text))?;
+        let key = match parse_scalar(&text[..=end])? {
+            Value::String(key) => key,
+            _ => unreachable!(),
+        };
+        (key, &text[end + 1..])
+    } else {
+        let end = text
+            .match_indices(':')
+            .map(|(idx, _)| idx)
+            .find(|&idx| text[idx + 1..].is_empty() || text[idx + 1..].starts_with(' '))
+            .ok_or_else(|| format!("expected `key: value`: {}", text))?;
+        (text[..end].trim_end().to_string(), &text[end..])
+    };
+
+    let rest = rest
+        .strip_prefix(':')
+        .filter(|rest| rest.is_empty() || rest.starts_with(' '))
+        .ok_or_else(|| format!("expected `key: value`: {}", text))?;
+    if key.is_empty() || key.starts_with(['-', '?', '[', '{', '&', '*', '!']) {
+        return Err(format!("unsupported key: {}", key));
+    }
+    Ok((key, rest.trim_start()))
+}
+
+fn parse_scalar(text: &str) -> Result<Value, String> {
+    match text.chars().next() {
+        Some('"') => parse_double_quoted(text).map(Value::String),
+        Some('\'') => {
+            let inner = text
+                .strip_prefix('\'')
+                .and_then(|t| t.strip_suffix('\''))
+                .filter(|inner| !inner.replace("''", "").contains('\''))
+                .ok_or_else(|| format!("invalid single-quoted string: {}", text))?;
+            Ok(Value::String(inner.replace("''", "'")))
+        }
+        Some('[') => {
+            let inner = text
+                .strip_prefix('[')
+                .and_then(|t| t.strip_suffix(']'))
+                .ok_or_else(|| format!("invalid flow sequence: {}", text))?;
+            if inner.trim().is_empty() {
+                return Ok(Value::Array(vec![]));
+            }
+            split_top_level(inner, ',')
+                .into_iter()
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| {
+                    if item.starts_with(['[', '{']) {
+                        Err(format!(
+                            "nested flow collections are not supported: {}",
+                            text
+                        ))
+                    } else {
+                        parse_scalar(item)
+                    }
+                })
+                .collect::<Result<_, _>>()
+                .map(Value::Array)
+        }
+        Some('{') if text == "{}" => Ok(Value::Table(vec![])),
+        Some('{' | '|' | '>' | '&' | '*' | '!' | '%' | '@' | '`') => {
+            Err(format!("unsupported value: {}", text))
+        }
+        _ if text.contains(" #") => Err("comments are not supported".into()),
+        _ if text.contains(": ") => Err(format!("unexpected mapping value: {}", text)),
+        _ if is_literal(text) => Ok(Value::Literal(text.into())),
+        _ => Ok(Value::String(text.into())),
+    }
+}
+
+/// Checks whether a plain scalar resolves to something other than a string
+fn is_literal(text: &str) -> bool {
+    text == "~"
+        || text.eq_ignore_ascii_case("null")
+        || matches!(text.to_ascii_lowercase().as_str(), "y" | "n")
+        || matches!(
+            Value::Literal(text.into()).kind(),
+            Kind::Bool | Kind::Number | Kind::Date
+        )
+}
+
+/// Writes a table as a YAML document, nesting by `indent` spaces
+pub fn write(value: &Value, indent: usize, quote: QuoteStyle) -> String {
+    let mut out = String::new();
+    if let Value::Table(entries) = value {
+        write_entries(entries, 0, indent, quote, &mut out);
+    }
+    out
+}
+
+fn write_entries(
+    entries: &[(String, Value)],
+    level: usize,
+    indent: usize,
+    quote: QuoteStyle,
+    out: &mut String,
+) {
+    let pad = " ".repeat(level);
+    for (key, value) in entries {
+        let key = write_key(key, quote);
+        match value {
+            Value::Table(entries) if !entries.is_empty() => {
+                out.push_str(&format!("{}{}:\n", pad, key));
+                write_entries(entries, level + indent, indent, quote, out);
+            }
+            Value::Array(items) if !items.is_empty() => {
+                out.push_str(&format!("{}{}:\n", pad, key));
+                write_items(items, level + indent, indent, quote, out);
+            }
+            scalar => out.push_str(&format!(
+                "{}{}: {}\n",
+                pad,
+                key,
+                write_scalar(scalar, quote)
+            )),
+        }
+    }
+}
+
+fn write_items(items: &[Value], level: usize, indent: usize, quote: QuoteStyle, out: &mut String) {
+    let pad = " ".repeat(level);
+    for item in items {
+        match item {
+            Value::Table(entries) if !entries.is_empty() => {
+                // The first entry shares the line with the item marker
+                let mut nested = String::new();
+                write_entries(entries, level + 2, indent, quote, &mut nested);
+                out.push_str(&format!("{}- {}", pad, &nested[level + 2..]));
+            }
+            Value::Array(items) if !items.is_empty() => {
+                out.push_str(&format!("{}-\n", pad));
+                write_items(items, level + indent, indent, quote, out);
+            }
+            scalar => out.push_str(&format!("{}- {}\n", pad, write_scalar(scalar, quote))),
+        }
+    }
+}
+
+fn write_key(key: &str, quote: QuoteStyle) -> String {
+    if is_plain_safe(key) {
+        key.into()
+    } else {
+        write_string(key, quote)
+    }
+}
+
+fn write_scalar(value: &Value, quote: QuoteStyle) -> String {
+    match value {
+        Value::String(text) if quote == QuoteStyle::Minimal && is_plain_safe(text) => text.clone(),
+        Value::String(text) => write_string(text, quote),
+        Value::Literal(raw) => raw.clone(),
+        Value::Array(_) => "[]".into(),
+        Value::Table(_) => "{}".into(),
+    }
+}
+
+fn write_string(text: &str, quote: QuoteStyle) -> String {
+    if quote == QuoteStyle::Single && !text.contains(char::is_control) {
+        format!("'{}'", text.replace('\'', "''"))
+    } else {
+        double_quote(text)
+    }
+}
+
+/// Checks whether a string can be written without quotes and read back as
+/// the same string
+fn is_plain_safe(text: &str) -> bool {
+    !text.is_empty()
+        && text.trim() == text
+        && !text.contains(char::is_control)
+        && !text.starts_with([
+            '-', '?', ':', ',', '[', ']', '{', '}', '#', '&', '*', '!', '|', '>', '\'', '"', '%',
+            '@', '`',
+        ])
+        && !text.contains(": ")
+        && !text.contains(" #")
+        && !text.ends_with(':')
+        && !is_literal(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let source = "\
+title: 'It''s here'
+date: 2024-01-31
+tags: [rust, \"cli tools\"]
+author:
+    name: Jane
+links:
+- url: https://example.com
+  label: Home
+- 3
+";
+        let string = |text: &str| Value::String(text.into());
+
+        assert_eq!(
+            parse(source),
+            Ok(Value::Table(vec![
+                ("title".into(), string("It's here")),
+                ("date".into(), Value::Literal("2024-01-31".into())),
+                (
+                    "tags".into(),
+                    Value::Array(vec![string("rust"), string("cli tools")])
+                ),
+                (
+                    "author".into(),
+                    Value::Table(vec![("name".into(), string("Jane"))])
+                ),
+                (
+                    "links".into(),
+                    Value::Array(vec![
+                        Value::Table(vec![
+                            ("url".into(), string("https://example.com")),
+                            ("label".into(), string("Home")),
+                        ]),
+                        Value::Literal("3".into()),
+                    ])
+                ),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_lossy_input() {
+        assert!(parse("a: 1 # note").is_err());
+        assert!(parse("# note\na: 1").is_err());
+        assert!(parse("a: |\n  text").is_err());
+        assert!(parse("a: &x 1").is_err());
+        assert!(parse("a: 1\n   b: 2").is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        let source = "title: \"Hello\"\nversion: '1.0'\nlinks:\n- url: x\n  label: y\nempty: []\n";
+        let value = parse(source).unwrap();
+
+        assert_eq!(
+            write(&value, 2, QuoteStyle::Minimal),
+            "title: Hello\nversion: \"1.0\"\nlinks:\n  - url: x\n    label: y\nempty: []\n"
+        );
+        assert_eq!(
+            write(&value, 4, QuoteStyle::Single),
+            "title: 'Hello'\nversion: '1.0'\nlinks:\n    - url: 'x'\n      label: y\nempty: []\n"
+        );
+        assert_eq!(parse(&write(&value, 2, QuoteStyle::Minimal)), Ok(value));
+    }
+}
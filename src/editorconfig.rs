@@ -0,0 +1,190 @@
+//! Reads the `.editorconfig` properties that have a formatter option
+//! counterpart. Unknown properties and values are ignored, as other tools
+//! may define their own. `indent_size` sets both the indent width and the
+//! indent of YAML front matter, unless it is `tab`.
+
+use crate::glob::Glob;
+use crate::options::{EndOfLine, IndentStyle, PartialOptions};
+use clap::ValueEnum;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = ".editorconfig";
+
+/// A parsed `.editorconfig` file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EditorConfig {
+    /// Directory the section globs are relative to
+    pub dir: PathBuf,
+    /// Stop looking for files in parent directories
+    pub root: bool,
+    /// `[glob]` sections and their properties, with lowercase keys and values
+    sections: Vec<(Glob, Vec<(String, String)>)>,
+}
+
+impl EditorConfig {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let source = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut editorconfig = Self::parse(&source);
+        editorconfig.dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(editorconfig)
+    }
+
+    /// Parses an `.editorconfig` file, skipping lines that are not
+    /// properties and sections whose glob is invalid
+    pub fn parse(source: &str) -> Self {
+        let mut editorconfig = EditorConfig::default();
+        let mut preamble = true;
+        let mut section = None;
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(pattern) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                preamble = false;
+                section = Glob::new(pattern).ok().map(|glob| {
+                    editorconfig.sections.push((glob, vec![]));
+                    editorconfig.sections.len() - 1
+                });
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim().to_ascii_lowercase();
+            match section {
+                Some(idx) => editorconfig.sections[idx].1.push((key, value)),
+                None if preamble && key == "root" => editorconfig.root = value == "true",
+                None => {}
+            }
+        }
+        editorconfig
+    }
+
+    /// Overwrites the options set by the sections matching `file`
+    pub fn apply_to(&self, options: &mut PartialOptions, file: &Path) {
+        let Ok(relative) = file.strip_prefix(&self.dir) else {
+            return;
+        };
+        for (glob, properties) in &self.sections {
+            if !glob.matches(relative) {
+                continue;
+            }
+            for (key, value) in properties {
+                apply_property(options, key, value);
+            }
+        }
+    }
+}
+
+fn apply_property(options: &mut PartialOptions, key: &str, value: &str) {
+    let parse_bool = |value: &str| match value {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    };
+    match key {
+        "max_line_length" => set(&mut options.line_width, value, |value| match value {
+            "off" => Some(usize::MAX),
+            width => width.parse().ok(),
+        }),
+        "end_of_line" => set(&mut options.end_of_line, value, |value| {
+            EndOfLine::from_str(value, true).ok()
+        }),
+        "indent_style" => set(&mut options.indent_style, value, |value| {
+            IndentStyle::from_str(value, true).ok()
+        }),
+        "indent_size" => {
+            let parse = |value: &str| value.parse().ok().filter(|size| *size > 0);
+            set(&mut options.indent_width, value, parse);
+            set(&mut options.front_matter_indent, value, parse);
+        }
+        "insert_final_newline" => set(&mut options.insert_final_newline, value, parse_bool),
+        "trim_trailing_whitespace" => set(&mut options.trim_trailing_whitespace, value, parse_bool),
+        _ => {}
+    }
+}
+
+/// Clears `option` for `unset`, and otherwise sets it to the parsed value
+/// unless it is invalid
+fn set<T>(option: &mut Option<T>, value: &str, parse: impl Fn(&str) -> Option<T>) {
+    if value == "unset" {
+        *option = None;
+    } else if let Some(value) = parse(value) {
+        *option = Some(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_to() {
+        let source = "\
+root = true
+
+[*]
+end_of_line = CRLF
+max_line_length = 100
+indent_style = tab
+indent_size = 4
+insert_final_newline = maybe
+
+; Later sections win
+[docs/**.md]
+max_line_length = off
+trim_trailing_whitespace = true
+
+[*.{md,markdown}]
+insert_final_newline = false
+end_of_line = unset
+indent_size = tab
+";
+        let editorconfig = EditorConfig::parse(source);
+        assert!(editorconfig.root);
+
+        let mut options = PartialOptions::default();
+        editorconfig.apply_to(&mut options, Path::new("docs/a/b.md"));
+        assert_eq!(options.line_width, Some(usize::MAX));
+        assert_eq!(options.trim_trailing_whitespace, Some(true));
+        assert_eq!(options.insert_final_newline, Some(false));
+        assert_eq!(options.end_of_line, None);
+        assert_eq!(options.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(options.indent_width, Some(4));
+        assert_eq!(options.front_matter_indent, Some(4));
+
+        let mut options = PartialOptions::default();
+        editorconfig.apply_to(&mut options, Path::new("README.txt"));
+        assert_eq!(options.line_width, Some(100));
+        assert_eq!(options.end_of_line, Some(EndOfLine::Crlf));
+        assert_eq!(options.insert_final_newline, None);
+    }
+
+    #[test]
+    fn test_skip_invalid() {
+        let source = "\
+root
+[*.md]
+max_line_length = 100
+not a property
+
+[[a]
+max_line_length = 60
+root = true
+
+[*.md
+insert_final_newline = false
+";
+        let editorconfig = EditorConfig::parse(source);
+        assert!(!editorconfig.root);
+
+        let mut options = PartialOptions::default();
+        editorconfig.apply_to(&mut options, Path::new("a.md"));
+        assert_eq!(options.line_width, Some(100));
+        assert_eq!(options.insert_final_newline, None);
+    }
+}
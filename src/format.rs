@@ -0,0 +1,134 @@
+use crate::options::{FormatOptions, HardBreakStyle};
+use crate::syntax::stmt::Stmt;
+use crate::syntax::{footnote, front_matter, list};
+
+/// Applies the document-level passes and converts the statements into Markdown
+pub fn format(mut stmts: Vec<Stmt>, options: &FormatOptions) -> String {
+    if options.normalize_front_matter {
+        for stmt in stmts.iter_mut() {
+            if let Stmt::FrontMatter {
+                format, content, ..
+            } = stmt
+            {
+                if let Some(normalized) = front_matter::normalize(*format, content, options) {
+                    *content = normalized;
+                }
+            }
+        }
+    }
+    if let Some(bullet) = options.list_marker.bullet() {
+        list::set_bullets(&mut stmts, bullet);
+    }
+    if options.renumber_footnotes {
+        footnote::renumber(&mut stmts);
+    }
+    if options.footnotes_at_end {
+        footnote::move_to_end(&mut stmts, options.renumber_footnotes);
+    }
+
+    let trimmed_options;
+    let options = if options.trim_trailing_whitespace {
+        // Trailing spaces would be stripped from hard breaks below
+        trimmed_options = FormatOptions {
+            hard_break: HardBreakStyle::Backslash,
+            ..options.clone()
+        };
+        &trimmed_options
+    } else {
+        options
+    };
+
+    let eol = options.end_of_line.as_str();
+    let blocks = stmts
+        .iter()
+        .map(|stmt| match stmt {
+            // Ignored regions keep their whitespace and line endings
+            Stmt::Verbatim { content } => content.clone(),
+            stmt => {
+                let mut block = stmt.to_markdown(options);
+                if options.trim_trailing_whitespace {
+                    block = block
+                        .split('\n')
+                        .map(str::trim_end)
+                        .collect::<Vec<_>>()
+                        .join("\n");
+                }
+                block.replace('\n', eol)
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut output = blocks.join(&eol.repeat(2));
+    if options.insert_final_newline && !output.is_empty() {
+        output.push_str(eol);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::{EndOfLine, FormatOptions, HardBreakStyle};
+    use crate::syntax::format;
+    use crate::syntax::stmt::Stmt;
+
+    #[test]
+    fn test_final_newline() {
+        let stmts = vec![Stmt::HtmlBlock {
+            content: "<br>".into(),
+        }];
+        let options = FormatOptions {
+            end_of_line: EndOfLine::Crlf,
+            ..Default::default()
+        };
+        assert_eq!(super::format(stmts, &options), "<br>\r\n");
+        assert_eq!(super::format(vec![], &options), "");
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let source = "# Title\n\nline  \nnext\n\n```\ncode  \n```\n";
+        assert_eq!(
+            format(source, &FormatOptions::default()),
+            "# Title\n\nline\\\nnext\n\n```\ncode  \n```"
+        );
+
+        let options = FormatOptions {
+            hard_break: HardBreakStyle::Spaces,
+            end_of_line: EndOfLine::Crlf,
+            insert_final_newline: false,
+            trim_trailing_whitespace: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            format(source, &options),
+            "# Title\r\n\r\nline\\\r\nnext\r\n\r\n```\r\ncode\r\n```"
+        );
+    }
+
+    #[test]
+    fn test_line_endings_round_trip() {
+        let source =
+            "# Title\r\n\r\nline  \r\nnext\r\n\r\n| a |\r\n|---|\r\n\r\n```\r\ncode\r\n```\r\n";
+        for end_of_line in [EndOfLine::Crlf, EndOfLine::Cr] {
+            let options = FormatOptions {
+                end_of_line,
+                ..Default::default()
+            };
+            let formatted = format(source, &options);
+            assert_eq!(
+                formatted,
+                format(source, &FormatOptions::default()).replace('\n', end_of_line.as_str())
+            );
+            assert_eq!(format(&formatted, &options), formatted);
+        }
+    }
+
+    #[test]
+    fn test_wide_characters() {
+        let source = "| 名前 | 説明 |\n|--|--|\n| 東京 | 首都です |\n\n😀 starts a paragraph\n";
+        assert_eq!(
+            format(source, &FormatOptions::default()),
+            "| 名前 | 説明     |\n| ---- | -------- |\n| 東京 | 首都です |\n\n😀 starts a paragraph"
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Rules from `.gitignore`, `.ignore` and `.mdfignore` files, which share
+//! the `.gitignore` syntax: one glob per line, `#` comments, `!` to
+//! re-include a path and a trailing `/` to match only directories. The last
+//! matching rule wins, and rules in nested files win over their parents.
+
+use crate::glob::Glob;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Ignore files read in each directory, each taking precedence over the ones
+/// before it
+pub const FILE_NAMES: [&str; 3] = [".gitignore", ".ignore", ".mdfignore"];
+
+#[derive(Clone, Debug, PartialEq)]
+struct Rule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IgnoreFile {
+    /// Directory the rules are relative to
+    dir: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    /// Parses the rules of an ignore file in `dir`, skipping lines that are
+    /// not valid globs
+    pub fn parse(source: &str, dir: &Path) -> Self {
+        let mut rules = vec![];
+        for line in source.lines() {
+            let line = line.trim_end_matches('\r');
+            // Trailing spaces are ignored unless escaped, and `Glob` reads
+            // the escapes
+            let trimmed = line.trim_end_matches(' ');
+            let line = if trimmed.ends_with('\\') && trimmed.len() < line.len() {
+                &line[..trimmed.len() + 1]
+            } else {
+                trimmed
+            };
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line),
+            };
+            let dir_only = pattern.ends_with('/');
+            if let Ok(glob) = Glob::new(pattern.trim_end_matches('/')) {
+                rules.push(Rule {
+                    glob,
+                    negated,
+                    dir_only,
+                });
+            }
+        }
+        Self {
+            dir: dir.to_path_buf(),
+            rules,
+        }
+    }
+
+    /// Reads the ignore files in `dir`, in order of precedence
+    pub fn load_dir(dir: &Path) -> io::Result<Vec<Self>> {
+        let mut files = vec![];
+        for name in FILE_NAMES {
+            let path = dir.join(name);
+            if path.is_file() {
+                files.push(Self::parse(&fs::read_to_string(path)?, dir));
+            }
+        }
+        Ok(files)
+    }
+
+    /// Reads the ignore files in the directories above `dir`, outermost
+    /// first, up to the root of the git repository `dir` is in. Outside a
+    /// repository, there are none.
+    pub fn load_ancestors(dir: &Path) -> io::Result<Vec<Self>> {
+        if dir.join(".git").exists() {
+            return Ok(vec![]);
+        }
+        let Some(repository) = dir
+            .ancestors()
+            .skip(1)
+            .position(|d| d.join(".git").exists())
+        else {
+            return Ok(vec![]);
+        };
+
+        let ancestors = dir.ancestors().skip(1).take(repository + 1);
+        let mut files = vec![];
+        for ancestor in ancestors.collect::<Vec<_>>().into_iter().rev() {
+            files.extend(Self::load_dir(ancestor)?);
+        }
+        Ok(files)
+    }
+
+    /// Returns whether the last rule matching `path` ignores it, or `None`
+    /// if no rule matches
+    fn matches(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.dir).ok()?;
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.matches(relative))
+            .map(|rule| !rule.negated)
+    }
+}
+
+/// Checks whether `files`, outermost first, ignore `path`
+pub fn is_ignored(files: &[IgnoreFile], path: &Path, is_dir: bool) -> bool {
+    files
+        .iter()
+        .rev()
+        .find_map(|file| file.matches(path, is_dir))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_ignored() {
+        let root = Path::new("/repo");
+        let files = [
+            IgnoreFile::parse(
+                "# Build output\ntarget/\n*.md\n!README.md\n/draft.md\n",
+                root,
+            ),
+            IgnoreFile::parse("!draft.md\n\\#notes.txt\nspace\\ \n", &root.join("docs")),
+        ];
+        let ignored = |path: &str, is_dir| is_ignored(&files, &root.join(path), is_dir);
+
+        assert!(ignored("target", true));
+        assert!(!ignored("target", false));
+        assert!(ignored("docs/guide.md", false));
+        assert!(!ignored("docs/README.md", false));
+        assert!(ignored("draft.md", false));
+        assert!(!ignored("docs/draft.md", false));
+        assert!(ignored("docs/#notes.txt", false));
+        assert!(!ignored("docs/notes.txt", false));
+        assert!(ignored("docs/space ", false));
+        assert!(!ignored("docs", true));
+    }
+}
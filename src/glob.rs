@@ -0,0 +1,219 @@
+//! Glob patterns matched against `/`-separated relative paths. `*` and `?`
+//! match within a path segment, `**` matches across segments,
+//! `[a-z]` and `[!a-z]` match one character from a class and `{a,b}`
+//! matches either alternative. A pattern without a `/` matches the file name
+//! in any directory, and a leading `/` or `./` only anchors the pattern.
+
+use std::path::{Component, Path};
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Char(char),
+    /// `?`
+    Any,
+    /// `*`
+    Star,
+    /// `**/`, matching zero or more whole segments
+    Segments,
+    /// Any other `**`, matching everything up to the rest of the pattern
+    Anything,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Glob {
+    /// One token list per expansion of the `{a,b}` alternatives
+    alternatives: Vec<Vec<Token>>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Result<Self, String> {
+        let anchored = pattern.trim_end_matches('/').contains('/');
+        let pattern = pattern
+            .strip_prefix("./")
+            .or_else(|| pattern.strip_prefix('/'))
+            .unwrap_or(pattern);
+        let pattern = if anchored {
+            pattern.to_string()
+        } else {
+            format!("**/{}", pattern)
+        };
+
+        let alternatives = expand_braces(&pattern)?
+            .iter()
+            .map(|expanded| tokenize(expanded))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("invalid glob `{}`: {}", pattern, e))?;
+        Ok(Self { alternatives })
+    }
+
+    /// Checks whether a relative path matches the pattern
+    pub fn matches(&self, path: &Path) -> bool {
+        let path = path
+            .components()
+            .filter_map(|c| match c {
+                Component::Normal(part) => Some(part.to_string_lossy()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+        let path = path.chars().collect::<Vec<_>>();
+        self.alternatives
+            .iter()
+            .any(|tokens| matches_tokens(tokens, &path))
+    }
+}
+
+/// Expands `{a,b}` alternatives into one pattern each
+fn expand_braces(pattern: &str) -> Result<Vec<String>, String> {
+    let Some(open) = pattern.find('{') else {
+        return Ok(vec![pattern.to_string()]);
+    };
+    let mut depth = 0;
+    let mut options = vec![];
+    let mut start = open + 1;
+    for (idx, ch) in pattern[open..].char_indices().map(|(i, c)| (i + open, c)) {
+        match ch {
+            '{' => depth += 1,
+            '}' if depth == 1 => {
+                options.push(&pattern[start..idx]);
+                let rest = expand_braces(&pattern[idx + 1..])?;
+                let mut expanded = vec![];
+                for option in options {
+                    for option in expand_braces(option)? {
+                        for rest in &rest {
+                            expanded.push(format!("{}{}{}", &pattern[..open], option, rest));
+                        }
+                    }
+                }
+                return Ok(expanded);
+            }
+            '}' => depth -= 1,
+            ',' if depth == 1 => {
+                options.push(&pattern[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    Err(format!("unclosed `{{` in `{}`", pattern))
+}
+
+fn tokenize(pattern: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = vec![];
+    let mut chars = pattern.chars().peekable();
+    while let Some(ch) = chars.next() {
+        let at_segment_start = matches!(
+            tokens.last(),
+            None | Some(Token::Char('/') | Token::Segments)
+        );
+        match ch {
+            '*' if chars.next_if_eq(&'*').is_some() => {
+                if at_segment_start && chars.next_if_eq(&'/').is_some() {
+                    tokens.push(Token::Segments);
+                } else {
+                    tokens.push(Token::Anything);
+                }
+            }
+            '*' => tokens.push(Token::Star),
+            '?' => tokens.push(Token::Any),
+            '[' => {
+                let negated = chars.next_if(|&c| c == '!' || c == '^').is_some();
+                let mut ranges = vec![];
+                loop {
+                    match chars.next() {
+                        Some(']') if !ranges.is_empty() => break,
+                        Some(start) => {
+                            if chars.peek() == Some(&'-') {
+                                chars.next();
+                                match chars.next() {
+                                    Some(']') => {
+                                        ranges.push((start, start));
+                                        ranges.push(('-', '-'));
+                                        break;
+                                    }
+                                    Some(end) => ranges.push((start, end)),
+                                    None => return Err("unclosed `[`".into()),
+                                }
+                            } else {
+                                ranges.push((start, start));
+                            }
+                        }
+                        None => return Err("unclosed `[`".into()),
+                    }
+                }
+                tokens.push(Token::Class { negated, ranges });
+            }
+            '\\' => match chars.next() {
+                Some(ch) => tokens.push(Token::Char(ch)),
+                None => return Err("trailing `\\`".into()),
+            },
+            ch => tokens.push(Token::Char(ch)),
+        }
+    }
+    Ok(tokens)
+}
+
+fn matches_tokens(tokens: &[Token], path: &[char]) -> bool {
+    let Some((token, rest)) = tokens.split_first() else {
+        return path.is_empty();
+    };
+    match token {
+        Token::Char(ch) => path.first() == Some(ch) && matches_tokens(rest, &path[1..]),
+        Token::Any => path.first().is_some_and(|&c| c != '/') && matches_tokens(rest, &path[1..]),
+        Token::Class { negated, ranges } => path.first().is_some_and(|&c| {
+            c != '/'
+                && ranges.iter().any(|&(start, end)| start <= c && c <= end) != *negated
+                && matches_tokens(rest, &path[1..])
+        }),
+        Token::Star => {
+            let segment = path.iter().position(|&c| c == '/').unwrap_or(path.len());
+            (0..=segment).any(|len| matches_tokens(rest, &path[len..]))
+        }
+        Token::Segments => {
+            matches_tokens(rest, path)
+                || path
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, &c)| c == '/' && matches_tokens(rest, &path[idx + 1..]))
+        }
+        Token::Anything => (0..=path.len()).any(|len| matches_tokens(rest, &path[len..])),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().matches(Path::new(path))
+    }
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("CHANGELOG.md", "CHANGELOG.md"));
+        assert!(matches("CHANGELOG.md", "docs/CHANGELOG.md"));
+        assert!(matches("/CHANGELOG.md", "CHANGELOG.md"));
+        assert!(!matches("/CHANGELOG.md", "docs/CHANGELOG.md"));
+        assert!(matches("*.md", "docs/guide.md"));
+        assert!(matches("docs/api/**", "docs/api/v1/index.md"));
+        assert!(!matches("docs/api/**", "docs/guide.md"));
+        assert!(matches("docs/**/*.md", "docs/index.md"));
+        assert!(matches("docs/**/*.md", "docs/a/b/index.md"));
+        assert!(!matches("docs/*.md", "docs/a/index.md"));
+        assert!(matches("*.{md,markdown}", "README.markdown"));
+        assert!(matches("post-[0-9]?.md", "post-1a.md"));
+        assert!(!matches("post-[!0-9].md", "post-1.md"));
+        assert!(matches("./docs/*.md", "docs/a.md"));
+        assert!(matches("docs/**.md", "docs/a/b.md"));
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(Glob::new("*.{md").is_err());
+        assert!(Glob::new("[abc").is_err());
+    }
+}
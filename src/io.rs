@@ -1,11 +1,45 @@
+use crate::config::Resolver;
+use crate::format::format;
+use crate::gitignore::{self, IgnoreFile};
+use crate::glob::Glob;
+use crate::options::FormatOptions;
+use crate::schema::Schema;
+use crate::syntax::ignore;
 use crate::syntax::lexer::Lexer;
 use crate::syntax::parser::Parser;
 use std::fs::{self, File};
 use std::io::{self, ErrorKind, Read};
 use std::path::{Path, PathBuf};
 
-/// Processes a single file (line by line tokenization)
-pub fn process_file(file: &Path) -> io::Result<()> {
+/// Which files directory walks skip, whatever their extension
+#[derive(Clone, Debug, PartialEq)]
+pub struct Discovery {
+    /// Globs of files and directories to skip, relative to the current
+    /// directory, or to the walked directory when it is outside of it
+    pub excludes: Vec<Glob>,
+}
+
+/// Checks the extension of a file against Markdown `extensions`, ignoring
+/// case and a leading dot
+fn is_markdown(path: &Path, extensions: &[String]) -> bool {
+    path.extension().is_some_and(|extension| {
+        extensions
+            .iter()
+            .any(|e| extension.eq_ignore_ascii_case(e.trim_start_matches('.')))
+    })
+}
+
+/// Checks whether a file found in a directory walk is Markdown, with the
+/// extensions configured for it
+fn has_configured_extension(file: &Path, resolver: &mut Resolver) -> io::Result<bool> {
+    let settings = resolver.resolve(file).map_err(io::Error::other)?;
+    Ok(is_markdown(file, &settings.options.extensions))
+}
+
+/// Processes a single file (line by line tokenization), reporting schema
+/// problems on stderr. Returns the number of problems found.
+pub fn process_file(file: &Path, options: &FormatOptions, schema: &Schema) -> io::Result<usize> {
+    let path = file;
     let mut file = File::open(file).map_err(|e| {
         io::Error::new(
             io::ErrorKind::NotFound,
@@ -17,68 +51,148 @@ pub fn process_file(file: &Path) -> io::Result<()> {
 
     let lexer = Lexer::new(&content);
     // let tokens = lexer.collect::<Vec<_>>();
-    let mut parser = Parser::new(lexer);
-    let res = parser.parse().unwrap();
-    res.iter().for_each(|s| {
-        println!("{}\n", s.to_markdown());
-    });
+    let mut parser = Parser::new(&content, lexer).wiki_links(options.wiki_links);
+    let res = parser.parse().map_err(|errors| {
+        let errors = errors
+            .iter()
+            .map(|error| format!("{}:{}", path.display(), error))
+            .collect::<Vec<_>>();
+        io::Error::new(ErrorKind::InvalidData, errors.join("\n"))
+    })?;
+    if ignore::ignores_file(&res) {
+        print!("{}", content);
+        return Ok(0);
+    }
+    let diagnostics = schema.check(&res);
+    for diagnostic in &diagnostics {
+        let (line, col) = diagnostic.pos;
+        eprintln!("{}:{}:{}: {}", path.display(), line, col, diagnostic.msg);
+    }
+    print!("{}", format(res, options));
 
-    Ok(())
+    Ok(diagnostics.len())
 }
 
-/// Recursively traverse a directory and return a list of `.md` files
-pub fn visit_dirs<P>(dir: P) -> io::Result<Vec<PathBuf>>
+/// Recursively traverse a directory and return a list of the files
+/// `is_markdown` accepts, skipping `.git`, paths matching the excludes and
+/// paths ignored by the `.gitignore`, `.ignore` and `.mdfignore` files of the
+/// directory, its subdirectories and its parents within the same repository
+pub fn visit_dirs<P>(
+    dir: P,
+    discovery: &Discovery,
+    is_markdown: &mut dyn FnMut(&Path) -> io::Result<bool>,
+) -> io::Result<Vec<PathBuf>>
 where
     P: AsRef<Path>,
 {
+    let dir = dir.as_ref();
+    // Ignore rules and excludes match against absolute paths, while the
+    // returned paths stay as given
+    let absolute = fs::canonicalize(dir)?;
+    let base = match std::env::current_dir().and_then(fs::canonicalize) {
+        Ok(cwd) if absolute.starts_with(&cwd) => cwd,
+        _ => absolute.clone(),
+    };
+    let mut ignores = IgnoreFile::load_ancestors(&absolute)?;
     let mut result = Vec::new();
-    let entries = fs::read_dir(dir)?;
+    walk(
+        dir,
+        &absolute,
+        &base,
+        discovery,
+        is_markdown,
+        &mut ignores,
+        &mut result,
+    )?;
+    Ok(result)
+}
 
+fn walk(
+    dir: &Path,
+    absolute: &Path,
+    base: &Path,
+    discovery: &Discovery,
+    is_markdown: &mut dyn FnMut(&Path) -> io::Result<bool>,
+    ignores: &mut Vec<IgnoreFile>,
+    result: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    let parent_ignores = ignores.len();
+    ignores.extend(IgnoreFile::load_dir(absolute)?);
+
+    let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
     for entry in entries {
-        let entry = entry?;
         let path = entry.path();
+        let absolute = absolute.join(entry.file_name());
+        let is_dir = path.is_dir();
+        let relative = absolute.strip_prefix(base).unwrap_or(&absolute);
+        if (is_dir && entry.file_name() == ".git")
+            || discovery.excludes.iter().any(|glob| glob.matches(relative))
+            || gitignore::is_ignored(ignores, &absolute, is_dir)
+        {
+            continue;
+        }
 
-        if path.is_dir() {
+        if is_dir {
             // Recursively visit subdirectories
-            let files = visit_dirs(&path)?;
-            result.extend(files);
-        } else if let Some(extension) = path.extension() {
-            // Check if the file has a `.md` extension
-            if extension == "md" {
-                result.push(path);
-            }
+            walk(
+                &path,
+                &absolute,
+                base,
+                discovery,
+                is_markdown,
+                ignores,
+                result,
+            )?;
+        } else if is_markdown(&path)? {
+            result.push(path);
         }
     }
-    Ok(result)
+
+    ignores.truncate(parent_ignores);
+    Ok(())
 }
 
-/// Process a list of files or folders, only processing `.md` files
-pub fn process_paths(paths: &[String]) -> io::Result<()> {
+/// Process a list of files or folders, only processing Markdown files found
+/// in folders, with the settings `resolver` finds for each. Files named
+/// explicitly are processed whatever their extension. Returns the number of
+/// problems found.
+pub fn process_paths(
+    paths: &[String],
+    discovery: &Discovery,
+    resolver: &mut Resolver,
+) -> io::Result<usize> {
+    let mut problems = 0;
     for path_str in paths {
         let path = Path::new(path_str);
 
         if path.is_dir() {
-            // Process folder recursively, filtering only `.md` files
-            match visit_dirs(path) {
+            // Process folder recursively, filtering only Markdown files
+            let mut configured = |file: &Path| has_configured_extension(file, resolver);
+            match visit_dirs(path, discovery, &mut configured) {
                 Ok(files) => {
                     for file in files {
-                        process_file(&file)?;
+                        let settings = resolver.resolve(&file).map_err(io::Error::other)?;
+                        problems += process_file(&file, &settings.options, &settings.schema)?;
                     }
                 }
                 Err(e) => {
-                    return Err(io::Error::new(
-                        ErrorKind::Other,
-                        format!("Error reading directory '{}': {}", path_str, e),
-                    ));
+                    return Err(io::Error::other(format!(
+                        "Error reading directory '{}': {}",
+                        path_str, e
+                    )));
                 }
             }
         } else if path.is_file() {
-            // Process individual file if it has a `.md` extension
-            if let Some(extension) = path.extension() {
-                if extension == "md" {
-                    process_file(path)?;
-                }
+            let settings = resolver.resolve(path).map_err(io::Error::other)?;
+            if !is_markdown(path, &settings.options.extensions) {
+                eprintln!(
+                    "Warning: {} does not have a Markdown extension ({}), formatting it anyway",
+                    path_str,
+                    settings.options.extensions.join(", ")
+                );
             }
+            problems += process_file(path, &settings.options, &settings.schema)?;
         } else {
             return Err(io::Error::new(
                 ErrorKind::NotFound,
@@ -86,5 +200,62 @@ pub fn process_paths(paths: &[String]) -> io::Result<()> {
             ));
         }
     }
-    Ok(())
+    Ok(problems)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_markdown() {
+        let extensions = crate::options::DEFAULT_EXTENSIONS.map(String::from);
+        assert!(is_markdown(Path::new("README.MD"), &extensions));
+        assert!(is_markdown(Path::new("docs/guide.markdown"), &extensions));
+        assert!(is_markdown(Path::new("page.mdx"), &extensions));
+        assert!(!is_markdown(Path::new("notes.txt"), &extensions));
+        assert!(!is_markdown(Path::new("md"), &extensions));
+
+        assert!(is_markdown(Path::new("notes.txt"), &[".txt".into()]));
+    }
+
+    #[test]
+    fn test_process_file_reports_syntax_errors() {
+        let path = std::env::temp_dir().join(format!("mdf-invalid-{}.md", std::process::id()));
+        fs::write(&path, "Text\n\n````rust\nlet\n```\n").unwrap();
+
+        let result = process_file(&path, &FormatOptions::default(), &Schema::default());
+        fs::remove_file(&path).unwrap();
+
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            format!("{}:3:1: Invalid code block", path.display())
+        );
+    }
+
+    #[test]
+    fn test_excludes_absolute_path() {
+        let root = std::env::temp_dir().join(format!("mdf-walk-{}", std::process::id()));
+        for path in ["docs/a.md", "docs/gen/g.md", "b.md"] {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let discovery = Discovery {
+            excludes: vec![Glob::new("docs/gen/**").unwrap()],
+        };
+        let extensions = ["md".to_string()];
+        let files = visit_dirs(&root, &discovery, &mut |file| {
+            Ok(is_markdown(file, &extensions))
+        });
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            files.unwrap(),
+            vec![root.join("b.md"), root.join("docs/a.md")]
+        );
+    }
 }
@@ -1,7 +1,24 @@
+mod config;
+mod data;
+mod editorconfig;
+mod format;
+mod gitignore;
+mod glob;
 mod io;
+mod options;
+mod schema;
 mod syntax;
+mod width;
 
 use clap::Parser;
+use config::{Config, Resolver};
+use glob::Glob;
+use io::Discovery;
+use options::{
+    EndOfLine, HardBreakStyle, HeadingStyle, IndentStyle, ListMarker, PartialOptions, ProseWrap,
+    QuoteStyle, StrikethroughDelimiter, TableStyle,
+};
+use std::path::PathBuf;
 use std::process;
 
 /// A simple CLI for processing files and folders
@@ -11,13 +28,176 @@ struct Cli {
     /// List of input files and folders (at least one is required)
     #[arg(required = true)]
     input: Vec<String>,
+
+    /// Skip files and directories matching this glob when walking
+    /// directories, in addition to those ignored by `.gitignore`, `.ignore`
+    /// and `.mdfignore` files
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Comma-separated extensions of the Markdown files to format when
+    /// walking directories [default: md, markdown, mdown, mdwn, mkd, mkdn,
+    /// mkdown, mdx]
+    #[arg(long, value_delimiter = ',')]
+    extensions: Option<Vec<String>>,
+
+    /// Path to a config file to use instead of the `mdf.toml` or `.mdf.toml`
+    /// files found above each input file
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Wrap bare URLs in angle brackets
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    wrap_bare_urls: Option<bool>,
+
+    /// Delimiter used for strikethrough text [default: double]
+    #[arg(long, value_enum)]
+    strikethrough_delimiter: Option<StrikethroughDelimiter>,
+
+    /// Style used for hard line breaks [default: backslash]
+    #[arg(long, value_enum)]
+    hard_break: Option<HardBreakStyle>,
+
+    /// How table columns are padded [default: aligned]
+    #[arg(long, value_enum)]
+    table_style: Option<TableStyle>,
+
+    /// Bullet written for unordered list items [default: preserve]
+    #[arg(long, value_enum)]
+    list_marker: Option<ListMarker>,
+
+    /// How headings are marked [default: atx]
+    #[arg(long, value_enum)]
+    heading_style: Option<HeadingStyle>,
+
+    /// Indent footnote and definition bodies with spaces or a tab
+    /// [default: space]
+    #[arg(long, value_enum)]
+    indent_style: Option<IndentStyle>,
+
+    /// Column list item content is aligned to, and the width of footnote and
+    /// definition body indents, which is at least 4 [default: 2]
+    #[arg(long)]
+    indent_width: Option<usize>,
+
+    /// Widest table kept aligned with `--table-style auto` [default: 80]
+    #[arg(long)]
+    table_max_width: Option<usize>,
+
+    /// Move footnote definitions to the end of the document
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    footnotes_at_end: Option<bool>,
+
+    /// Renumber numeric footnotes in order of first reference
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    renumber_footnotes: Option<bool>,
+
+    /// Parse Obsidian-style `[[wiki links]]` and `![[embeds]]`
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    wiki_links: Option<bool>,
+
+    /// Rewrite YAML and TOML front matter in a consistent style
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    normalize_front_matter: Option<bool>,
+
+    /// Comma-separated front matter keys to write first, in order
+    #[arg(long, value_delimiter = ',')]
+    front_matter_key_order: Option<Vec<String>>,
+
+    /// How normalized front matter strings are quoted [default: minimal]
+    #[arg(long, value_enum)]
+    front_matter_quote: Option<QuoteStyle>,
+
+    /// Indentation of nested YAML in normalized front matter [default: 2]
+    #[arg(long)]
+    front_matter_indent: Option<usize>,
+
+    /// How paragraph text is broken into lines [default: preserve]
+    #[arg(long, value_enum)]
+    prose_wrap: Option<ProseWrap>,
+
+    /// Line width paragraphs are filled to with `--prose-wrap always`
+    /// [default: 80]
+    #[arg(long)]
+    line_width: Option<usize>,
+
+    /// Comma-separated words ending in a period that do not end a sentence
+    /// with `--prose-wrap sentence` [default: common English abbreviations]
+    #[arg(long, value_delimiter = ',')]
+    abbreviations: Option<Vec<String>>,
+
+    /// Line ending written after every line [default: lf]
+    #[arg(long, value_enum)]
+    end_of_line: Option<EndOfLine>,
+
+    /// End the output with a line ending [default: true]
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    insert_final_newline: Option<bool>,
+
+    /// Strip trailing whitespace from every line, including in code blocks
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    trim_trailing_whitespace: Option<bool>,
+}
+
+impl Cli {
+    /// Collects the formatter options given on the command line, which
+    /// override those set in config files
+    fn options(&self) -> PartialOptions {
+        PartialOptions {
+            wrap_bare_urls: self.wrap_bare_urls,
+            strikethrough_delimiter: self.strikethrough_delimiter,
+            hard_break: self.hard_break,
+            table_style: self.table_style,
+            list_marker: self.list_marker,
+            heading_style: self.heading_style,
+            indent_style: self.indent_style,
+            indent_width: self.indent_width,
+            table_max_width: self.table_max_width,
+            footnotes_at_end: self.footnotes_at_end,
+            renumber_footnotes: self.renumber_footnotes,
+            wiki_links: self.wiki_links,
+            normalize_front_matter: self.normalize_front_matter,
+            front_matter_key_order: self.front_matter_key_order.clone(),
+            front_matter_quote: self.front_matter_quote,
+            front_matter_indent: self.front_matter_indent,
+            prose_wrap: self.prose_wrap,
+            line_width: self.line_width,
+            abbreviations: self.abbreviations.clone(),
+            end_of_line: self.end_of_line,
+            insert_final_newline: self.insert_final_newline,
+            trim_trailing_whitespace: self.trim_trailing_whitespace,
+            extensions: self.extensions.clone(),
+        }
+    }
 }
 
 fn main() {
     let args = Cli::parse();
 
-    if let Err(e) = io::process_paths(&args.input) {
-        eprintln!("Error: {}", e);
-        process::exit(1);
+    let explicit = args.config.as_ref().map(|path| {
+        Config::load(path).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        })
+    });
+    let excludes = args
+        .exclude
+        .iter()
+        .map(|pattern| Glob::new(pattern))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        });
+    let discovery = Discovery { excludes };
+    let mut resolver = Resolver::new(args.options(), explicit);
+
+    match io::process_paths(&args.input, &discovery, &mut resolver) {
+        Ok(0) => {}
+        Ok(_) => process::exit(1),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            process::exit(1);
+        }
     }
 }
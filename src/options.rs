@@ -0,0 +1,341 @@
+use clap::ValueEnum;
+
+/// Abbreviations that do not end a sentence unless configured otherwise
+pub const DEFAULT_ABBREVIATIONS: [&str; 15] = [
+    "e.g.", "i.e.", "cf.", "vs.", "approx.", "Dr.", "Mr.", "Mrs.", "Ms.", "Prof.", "Jr.", "Sr.",
+    "St.", "No.", "Fig.",
+];
+
+/// Extensions of the files picked up when walking directories, unless
+/// configured otherwise
+pub const DEFAULT_EXTENSIONS: [&str; 8] = [
+    "md", "markdown", "mdown", "mdwn", "mkd", "mkdn", "mkdown", "mdx",
+];
+
+/// Options controlling how a parsed document is written back out. Emphasis
+/// is always kept as written, since `*` and `_` are not parsed as emphasis.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatOptions {
+    /// Wrap bare `http://` and `https://` URLs in angle brackets
+    pub wrap_bare_urls: bool,
+    /// Delimiter written around strikethrough text
+    pub strikethrough_delimiter: StrikethroughDelimiter,
+    /// How hard line breaks are written
+    pub hard_break: HardBreakStyle,
+    /// How table columns are padded
+    pub table_style: TableStyle,
+    /// Bullet written for unordered list items
+    pub list_marker: ListMarker,
+    /// How headings are marked
+    pub heading_style: HeadingStyle,
+    /// Whether footnote and definition bodies are indented with spaces or a
+    /// tab
+    pub indent_style: IndentStyle,
+    /// Column list item content is aligned to after short markers, and the
+    /// indent of footnote and definition bodies with spaces, which is never
+    /// less than the four columns they need
+    pub indent_width: usize,
+    /// Widest aligned table allowed by `TableStyle::Auto`
+    pub table_max_width: usize,
+    /// Move footnote definitions to the end of the document
+    pub footnotes_at_end: bool,
+    /// Renumber numeric footnotes in order of first reference
+    pub renumber_footnotes: bool,
+    /// Parse `[[wiki links]]` and `![[embeds]]` as links
+    pub wiki_links: bool,
+    /// Rewrite YAML and TOML front matter in a consistent style
+    pub normalize_front_matter: bool,
+    /// Front matter keys written first, in this order
+    pub front_matter_key_order: Vec<String>,
+    /// How front matter strings are quoted
+    pub front_matter_quote: QuoteStyle,
+    /// Indentation of nested YAML front matter
+    pub front_matter_indent: usize,
+    /// How paragraph text is broken into lines
+    pub prose_wrap: ProseWrap,
+    /// Widest line `ProseWrap::Always` fills paragraphs to
+    pub line_width: usize,
+    /// Words ending in a period that do not end a sentence with
+    /// `ProseWrap::Sentence`
+    pub abbreviations: Vec<String>,
+    /// Line ending written after every line
+    pub end_of_line: EndOfLine,
+    /// End the document with a line ending
+    pub insert_final_newline: bool,
+    /// Strip trailing whitespace from every line, including in code blocks.
+    /// Hard breaks are then written with a backslash.
+    pub trim_trailing_whitespace: bool,
+    /// Extensions of the Markdown files formatted when walking directories,
+    /// without the leading dot
+    pub extensions: Vec<String>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            wrap_bare_urls: false,
+            strikethrough_delimiter: StrikethroughDelimiter::default(),
+            hard_break: HardBreakStyle::default(),
+            table_style: TableStyle::default(),
+            list_marker: ListMarker::default(),
+            heading_style: HeadingStyle::default(),
+            indent_style: IndentStyle::default(),
+            indent_width: 2,
+            table_max_width: 80,
+            footnotes_at_end: false,
+            renumber_footnotes: false,
+            wiki_links: false,
+            normalize_front_matter: false,
+            front_matter_key_order: vec![],
+            front_matter_quote: QuoteStyle::default(),
+            front_matter_indent: 2,
+            prose_wrap: ProseWrap::default(),
+            line_width: 80,
+            abbreviations: DEFAULT_ABBREVIATIONS
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+            end_of_line: EndOfLine::default(),
+            insert_final_newline: true,
+            trim_trailing_whitespace: false,
+            extensions: DEFAULT_EXTENSIONS.map(String::from).to_vec(),
+        }
+    }
+}
+
+/// Formatter options set by one source, such as a config file or the command
+/// line. Unset options are left to the sources below it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PartialOptions {
+    pub wrap_bare_urls: Option<bool>,
+    pub strikethrough_delimiter: Option<StrikethroughDelimiter>,
+    pub hard_break: Option<HardBreakStyle>,
+    pub table_style: Option<TableStyle>,
+    pub list_marker: Option<ListMarker>,
+    pub heading_style: Option<HeadingStyle>,
+    pub indent_style: Option<IndentStyle>,
+    pub indent_width: Option<usize>,
+    pub table_max_width: Option<usize>,
+    pub footnotes_at_end: Option<bool>,
+    pub renumber_footnotes: Option<bool>,
+    pub wiki_links: Option<bool>,
+    pub normalize_front_matter: Option<bool>,
+    pub front_matter_key_order: Option<Vec<String>>,
+    pub front_matter_quote: Option<QuoteStyle>,
+    pub front_matter_indent: Option<usize>,
+    pub prose_wrap: Option<ProseWrap>,
+    pub line_width: Option<usize>,
+    pub abbreviations: Option<Vec<String>>,
+    pub end_of_line: Option<EndOfLine>,
+    pub insert_final_newline: Option<bool>,
+    pub trim_trailing_whitespace: Option<bool>,
+    pub extensions: Option<Vec<String>>,
+}
+
+impl PartialOptions {
+    /// Overwrites the options that are set here
+    pub fn apply_to(&self, options: &mut FormatOptions) {
+        if let Some(wrap_bare_urls) = &self.wrap_bare_urls {
+            options.wrap_bare_urls = *wrap_bare_urls;
+        }
+        if let Some(strikethrough_delimiter) = &self.strikethrough_delimiter {
+            options.strikethrough_delimiter = *strikethrough_delimiter;
+        }
+        if let Some(hard_break) = &self.hard_break {
+            options.hard_break = *hard_break;
+        }
+        if let Some(table_style) = &self.table_style {
+            options.table_style = *table_style;
+        }
+        if let Some(list_marker) = &self.list_marker {
+            options.list_marker = *list_marker;
+        }
+        if let Some(heading_style) = &self.heading_style {
+            options.heading_style = *heading_style;
+        }
+        if let Some(indent_style) = &self.indent_style {
+            options.indent_style = *indent_style;
+        }
+        if let Some(indent_width) = &self.indent_width {
+            options.indent_width = *indent_width;
+        }
+        if let Some(table_max_width) = &self.table_max_width {
+            options.table_max_width = *table_max_width;
+        }
+        if let Some(footnotes_at_end) = &self.footnotes_at_end {
+            options.footnotes_at_end = *footnotes_at_end;
+        }
+        if let Some(renumber_footnotes) = &self.renumber_footnotes {
+            options.renumber_footnotes = *renumber_footnotes;
+        }
+        if let Some(wiki_links) = &self.wiki_links {
+            options.wiki_links = *wiki_links;
+        }
+        if let Some(normalize_front_matter) = &self.normalize_front_matter {
+            options.normalize_front_matter = *normalize_front_matter;
+        }
+        if let Some(front_matter_key_order) = &self.front_matter_key_order {
+            options.front_matter_key_order = front_matter_key_order.clone();
+        }
+        if let Some(front_matter_quote) = &self.front_matter_quote {
+            options.front_matter_quote = *front_matter_quote;
+        }
+        if let Some(front_matter_indent) = &self.front_matter_indent {
+            options.front_matter_indent = *front_matter_indent;
+        }
+        if let Some(prose_wrap) = &self.prose_wrap {
+            options.prose_wrap = *prose_wrap;
+        }
+        if let Some(line_width) = &self.line_width {
+            options.line_width = *line_width;
+        }
+        if let Some(abbreviations) = &self.abbreviations {
+            options.abbreviations = abbreviations.clone();
+        }
+        if let Some(end_of_line) = &self.end_of_line {
+            options.end_of_line = *end_of_line;
+        }
+        if let Some(insert_final_newline) = &self.insert_final_newline {
+            options.insert_final_newline = *insert_final_newline;
+        }
+        if let Some(trim_trailing_whitespace) = &self.trim_trailing_whitespace {
+            options.trim_trailing_whitespace = *trim_trailing_whitespace;
+        }
+        if let Some(extensions) = &self.extensions {
+            options.extensions = extensions.clone();
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum StrikethroughDelimiter {
+    /// `~text~`
+    Single,
+    /// `~~text~~`
+    #[default]
+    Double,
+}
+
+impl StrikethroughDelimiter {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StrikethroughDelimiter::Single => "~",
+            StrikethroughDelimiter::Double => "~~",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum HardBreakStyle {
+    /// A trailing backslash
+    #[default]
+    Backslash,
+    /// Two trailing spaces
+    Spaces,
+}
+
+impl HardBreakStyle {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HardBreakStyle::Backslash => "\\\n",
+            HardBreakStyle::Spaces => "  \n",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum TableStyle {
+    /// Pad every column to the same width
+    #[default]
+    Aligned,
+    /// Single spaces around cells, no padding
+    Compact,
+    /// Aligned, unless a row would exceed the table max width
+    Auto,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ListMarker {
+    /// Keep bullets as written
+    #[default]
+    Preserve,
+    /// `-`
+    Dash,
+    /// `*`
+    Asterisk,
+    /// `+`
+    Plus,
+}
+
+impl ListMarker {
+    /// Returns the bullet every unordered list is written with, if any
+    pub fn bullet(&self) -> Option<char> {
+        match self {
+            ListMarker::Preserve => None,
+            ListMarker::Dash => Some('-'),
+            ListMarker::Asterisk => Some('*'),
+            ListMarker::Plus => Some('+'),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum HeadingStyle {
+    /// Leading `#` markers
+    #[default]
+    Atx,
+    /// `===` and `---` underlines for the first two levels, `#` markers below
+    Setext,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum IndentStyle {
+    #[default]
+    Space,
+    Tab,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum QuoteStyle {
+    /// Quote strings only where needed, with double quotes
+    #[default]
+    Minimal,
+    /// Always use double quotes
+    Double,
+    /// Always use single quotes, unless the string cannot be written in them
+    Single,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum ProseWrap {
+    /// Reflow paragraphs to fill the line width
+    Always,
+    /// Join each paragraph into a single line
+    Never,
+    /// Keep line breaks as written
+    #[default]
+    Preserve,
+    /// Put every sentence on its own line
+    Sentence,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum EndOfLine {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    Crlf,
+    /// `\r`
+    Cr,
+}
+
+impl EndOfLine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndOfLine::Lf => "\n",
+            EndOfLine::Crlf => "\r\n",
+            EndOfLine::Cr => "\r",
+        }
+    }
+}
@@ -0,0 +1,253 @@
+use crate::data::{self, json, toml, yaml, Kind, Value};
+use crate::syntax::front_matter::FrontMatterFormat;
+use crate::syntax::stmt::Stmt;
+
+/// Required and typed front matter keys declared in the config
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Schema {
+    pub required: Vec<String>,
+    pub types: Vec<(String, Kind)>,
+}
+
+/// A problem found in a document, reported as `path:line:col: msg`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub pos: (usize, usize),
+    pub msg: String,
+}
+
+impl Schema {
+    /// Reads the `[front-matter]` section of a config file
+    pub fn from_config(section: &Value) -> Result<Self, String> {
+        let Value::Table(entries) = section else {
+            return Err("`front-matter` must be a table".into());
+        };
+
+        let mut schema = Schema::default();
+        for (key, value) in entries {
+            match (key.as_str(), value) {
+                ("required", Value::Array(keys)) => {
+                    for key in keys {
+                        match key {
+                            Value::String(key) => schema.required.push(key.clone()),
+                            _ => return Err("`front-matter.required` must list strings".into()),
+                        }
+                    }
+                }
+                ("types", Value::Table(types)) => {
+                    for (key, kind) in types {
+                        let kind = match kind {
+                            Value::String(name) => parse_kind(name)?,
+                            _ => return Err(format!("type of `{}` must be a string", key)),
+                        };
+                        schema.types.push((key.clone(), kind));
+                    }
+                }
+                _ => return Err(format!("unknown config key `front-matter.{}`", key)),
+            }
+        }
+        Ok(schema)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.required.is_empty() && self.types.is_empty()
+    }
+
+    /// Checks the front matter of a parsed document against the schema
+    pub fn check(&self, stmts: &[Stmt]) -> Vec<Diagnostic> {
+        if self.is_empty() {
+            return vec![];
+        }
+        let Some(Stmt::FrontMatter {
+            format,
+            content,
+            pos,
+        }) = stmts.first()
+        else {
+            return match self.required.is_empty() {
+                true => vec![],
+                false => vec![Diagnostic {
+                    pos: (1, 1),
+                    msg: "missing front matter".into(),
+                }],
+            };
+        };
+
+        let diagnostic = |pos, msg| vec![Diagnostic { pos, msg }];
+        let value = match format {
+            FrontMatterFormat::Yaml => yaml::parse(content),
+            FrontMatterFormat::Toml => toml::parse_lenient(content),
+            FrontMatterFormat::Json { .. } => json::parse(content),
+        };
+        let value = match value {
+            Ok(value) => value,
+            Err(e) => return diagnostic(*pos, format!("front matter could not be read: {}", e)),
+        };
+
+        let mut diagnostics = vec![];
+        for key in &self.required {
+            if value.get(key).is_none_or(|v| v.kind() == Kind::Null) {
+                diagnostics.push(Diagnostic {
+                    pos: *pos,
+                    msg: format!("missing required front matter key `{}`", key),
+                });
+            }
+        }
+        for (key, expected) in &self.types {
+            let Some(value) = value.get(key).filter(|v| v.kind() != Kind::Null) else {
+                continue;
+            };
+            if !matches_kind(value, *expected) {
+                diagnostics.push(Diagnostic {
+                    pos: (
+                        key_line(*format, content, key).map_or(pos.0, |l| pos.0 + l),
+                        1,
+                    ),
+                    msg: format!(
+                        "front matter key `{}` should be {}, found {}",
+                        key,
+                        describe(*expected),
+                        describe(value.kind())
+                    ),
+                });
+            }
+        }
+        diagnostics
+    }
+}
+
+fn parse_kind(name: &str) -> Result<Kind, String> {
+    Ok(match name {
+        "string" => Kind::String,
+        "number" => Kind::Number,
+        "bool" | "boolean" => Kind::Bool,
+        "date" => Kind::Date,
+        "list" | "array" => Kind::Array,
+        "table" | "map" => Kind::Table,
+        _ => return Err(format!("unknown front matter type `{}`", name)),
+    })
+}
+
+fn describe(kind: Kind) -> &'static str {
+    match kind {
+        Kind::String => "a string",
+        Kind::Number => "a number",
+        Kind::Bool => "a boolean",
+        Kind::Date => "a date",
+        Kind::Null => "nothing",
+        Kind::Array => "a list",
+        Kind::Table => "a table",
+    }
+}
+
+/// Quoted dates are common enough in front matter to count as dates
+fn matches_kind(value: &Value, expected: Kind) -> bool {
+    match value {
+        Value::String(text) if expected == Kind::Date => data::is_date(text),
+        value => value.kind() == expected,
+    }
+}
+
+/// Finds the line of a top-level key within the front matter, counting the
+/// opening delimiter as line 0. JSON keys are indented, so the first line
+/// with the key is taken.
+fn key_line(format: FrontMatterFormat, content: &str, key: &str) -> Option<usize> {
+    let separator = match format {
+        FrontMatterFormat::Toml => '=',
+        _ => ':',
+    };
+    let json = matches!(format, FrontMatterFormat::Json { .. });
+    let idx = content.lines().position(|line| {
+        let name = line.split(separator).next().unwrap_or_default().trim();
+        name.trim_matches(['"', '\'']) == key && (json || !line.starts_with(char::is_whitespace))
+    })?;
+    // Bare JSON content starts with the opening brace itself
+    match format {
+        FrontMatterFormat::Json { fenced: false } => Some(idx),
+        _ => Some(idx + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::lexer::Lexer;
+    use crate::syntax::parser::Parser;
+
+    fn check(schema: &Schema, input: &str) -> Vec<Diagnostic> {
+        schema.check(&Parser::new(input, Lexer::new(input)).parse().unwrap())
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config =
+            toml::parse("required = [\"title\"]\n\n[types]\ntitle = \"string\"\ntags = \"list\"\n")
+                .unwrap();
+
+        assert_eq!(
+            Schema::from_config(&config),
+            Ok(Schema {
+                required: vec!["title".into()],
+                types: vec![("title".into(), Kind::String), ("tags".into(), Kind::Array)],
+            })
+        );
+        assert!(Schema::from_config(&toml::parse("[types]\na = \"text\"").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_check() {
+        let schema = Schema {
+            required: vec!["title".into(), "date".into()],
+            types: vec![
+                ("date".into(), Kind::Date),
+                ("tags".into(), Kind::Array),
+                ("draft".into(), Kind::Bool),
+            ],
+        };
+
+        assert_eq!(
+            check(
+                &schema,
+                "---\ndate: '2024-01-31'\ntags: rust\ndraft: false\n---\n\nText"
+            ),
+            vec![
+                Diagnostic {
+                    pos: (1, 1),
+                    msg: "missing required front matter key `title`".into()
+                },
+                Diagnostic {
+                    pos: (3, 1),
+                    msg: "front matter key `tags` should be a list, found a string".into()
+                },
+            ]
+        );
+        assert_eq!(
+            check(&schema, "+++\ntitle = \"T\"\ndate = 2024-01-31\n+++"),
+            vec![]
+        );
+        assert_eq!(
+            check(
+                &schema,
+                "{\n  \"title\": \"T\",\n  \"date\": \"2024-01-31\",\n  \"draft\": \"no\"\n}\n\nText"
+            ),
+            vec![Diagnostic {
+                pos: (4, 1),
+                msg: "front matter key `draft` should be a boolean, found a string".into()
+            }]
+        );
+        assert_eq!(
+            check(
+                &schema,
+                ";;;\n{\"title\": \"T\", \"date\": \"2024-01-31\"}\n;;;"
+            ),
+            vec![]
+        );
+        assert_eq!(
+            check(&schema, "No front matter"),
+            vec![Diagnostic {
+                pos: (1, 1),
+                msg: "missing front matter".into()
+            }]
+        );
+    }
+}
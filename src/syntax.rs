@@ -1,4 +1,29 @@
+pub mod attributes;
+pub mod definition;
+mod escape;
+pub mod footnote;
+pub mod front_matter;
+mod html;
+pub mod ignore;
+mod inline;
 pub mod lexer;
+pub mod list;
 pub mod parser;
-mod stmt;
+mod quote;
+pub mod stmt;
+mod table;
 mod tokens;
+mod wrap;
+
+/// Formats `source` as a whole document, without the final newline
+#[cfg(test)]
+pub fn format(source: &str, options: &crate::options::FormatOptions) -> String {
+    let stmts = parser::Parser::new(source, lexer::Lexer::new(source))
+        .parse()
+        .unwrap();
+    let options = crate::options::FormatOptions {
+        insert_final_newline: false,
+        ..options.clone()
+    };
+    crate::format::format(stmts, &options)
+}
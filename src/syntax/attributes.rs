@@ -0,0 +1,163 @@
+/// A `{#id .class key=value}` attribute block, as written after headings
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub pairs: Vec<(String, String)>,
+}
+
+impl Attributes {
+    pub fn is_empty(&self) -> bool {
+        self.id.is_none() && self.classes.is_empty() && self.pairs.is_empty()
+    }
+
+    /// Writes the attributes with single spaces, the id first, then classes,
+    /// then key-value pairs
+    pub fn to_markdown(&self) -> String {
+        let id = self.id.iter().map(|id| format!("#{}", id));
+        let classes = self.classes.iter().map(|class| format!(".{}", class));
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, quote(value)));
+        format!(
+            "{{{}}}",
+            id.chain(classes).chain(pairs).collect::<Vec<_>>().join(" ")
+        )
+    }
+}
+
+/// Splits a trailing attribute block off heading text, returning the text
+/// before it and the parsed attributes
+pub fn split_trailing(text: &str) -> Option<(&str, Attributes)> {
+    let trimmed = text.trim_end();
+    let body = trimmed.strip_suffix('}')?;
+    let open = body.rfind('{')?;
+    let before = &body[..open];
+    if !before.is_empty() && !before.ends_with([' ', '\t']) {
+        return None;
+    }
+
+    let attributes = parse(&body[open + 1..])?;
+    Some((before.trim_end(), attributes))
+}
+
+/// Parses the inside of an attribute block
+fn parse(text: &str) -> Option<Attributes> {
+    let mut attributes = Attributes::default();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(&first) = chars.peek() else {
+            break;
+        };
+
+        let mut word = String::new();
+        while let Some(ch) = chars.next_if(|c| !c.is_whitespace() && *c != '=') {
+            word.push(ch);
+        }
+
+        if chars.next_if_eq(&'=').is_some() {
+            if word.is_empty() || !word.chars().all(is_name_char) {
+                return None;
+            }
+            let value = parse_value(&mut chars)?;
+            attributes.pairs.push((word, value));
+        } else if let Some(id) = word.strip_prefix('#').filter(|_| first == '#') {
+            if id.is_empty() || attributes.id.is_some() {
+                return None;
+            }
+            attributes.id = Some(id.into());
+        } else if let Some(class) = word.strip_prefix('.') {
+            if class.is_empty() {
+                return None;
+            }
+            attributes.classes.push(class.into());
+        } else if word == "-" {
+            // Pandoc shorthand for an unnumbered heading
+            attributes.classes.push("unnumbered".into());
+        } else {
+            return None;
+        }
+    }
+
+    (!attributes.is_empty()).then_some(attributes)
+}
+
+/// Parses a bare or quoted attribute value
+fn parse_value(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<String> {
+    let mut value = String::new();
+    match chars.peek() {
+        Some(&quote @ ('"' | '\'')) => {
+            chars.next();
+            loop {
+                match chars.next()? {
+                    '\\' => value.push(chars.next()?),
+                    ch if ch == quote => break,
+                    ch => value.push(ch),
+                }
+            }
+            // A closing quote must end the value
+            if chars.peek().is_some_and(|c| !c.is_whitespace()) {
+                return None;
+            }
+        }
+        _ => {
+            while let Some(ch) = chars.next_if(|c| !c.is_whitespace()) {
+                value.push(ch);
+            }
+        }
+    }
+    Some(value)
+}
+
+fn is_name_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '-' | '_' | ':' | '.')
+}
+
+/// Quotes a value when it cannot be written bare
+fn quote(value: &str) -> String {
+    if !value.is_empty()
+        && !value.contains(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '{' | '}'))
+    {
+        return value.into();
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_trailing() {
+        let (text, attributes) =
+            split_trailing("Install  {.note   #setup data-x=1 title=\"A b\"}").unwrap();
+
+        assert_eq!(text, "Install");
+        assert_eq!(
+            attributes,
+            Attributes {
+                id: Some("setup".into()),
+                classes: vec!["note".into()],
+                pairs: vec![
+                    ("data-x".into(), "1".into()),
+                    ("title".into(), "A b".into())
+                ],
+            }
+        );
+        assert_eq!(
+            attributes.to_markdown(),
+            "{#setup .note data-x=1 title=\"A b\"}"
+        );
+    }
+
+    #[test]
+    fn test_split_trailing_rejects_non_attributes() {
+        assert_eq!(split_trailing("Set {a, b}"), None);
+        assert_eq!(split_trailing("Map{#id}"), None);
+        assert_eq!(split_trailing("Empty {}"), None);
+        assert_eq!(split_trailing("Two ids {#a #b}"), None);
+    }
+}
@@ -0,0 +1,122 @@
+use super::inline::{self, Inline};
+use super::stmt::{self, Stmt};
+use crate::options::{FormatOptions, ProseWrap};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DefinitionItem {
+    pub term: Vec<Inline>,
+    pub definitions: Vec<Vec<Stmt>>,
+    /// Whether blank lines separate the term and its definitions
+    pub loose: bool,
+}
+
+/// Returns the text after the marker of a `: definition` line
+pub fn parse_definition_start(line: &str) -> Option<String> {
+    let text = line.trim_start_matches(' ');
+    if line.len() - text.len() > 3 {
+        return None;
+    }
+
+    let rest = text.strip_prefix(':')?;
+    if !rest.starts_with([' ', '\t']) {
+        return None;
+    }
+    Some(rest.trim_start().into())
+}
+
+/// Checks whether `lines`, which start right after a term line, begin with a
+/// definition, allowing one blank line in between
+pub fn follows_term(mut lines: impl Iterator<Item = String>) -> bool {
+    let Some(mut next) = lines.next() else {
+        return false;
+    };
+    if next.trim().is_empty() {
+        next = lines.next().unwrap_or_default();
+    }
+    parse_definition_start(&next).is_some()
+}
+
+/// Converts a definition list into Markdown, normalising every marker so
+/// that the text after it lines up with the body indent.
+pub fn to_markdown(items: &[DefinitionItem], options: &FormatOptions) -> String {
+    let indent = stmt::body_indent(options);
+    // The marker takes the first column of an indent of spaces
+    let marker = format!(":{}", indent.strip_prefix(' ').unwrap_or(&indent));
+    // Terms must stay on a single line
+    let term_options = FormatOptions {
+        prose_wrap: ProseWrap::Never,
+        ..options.clone()
+    };
+    items
+        .iter()
+        .map(|item| {
+            let separator = if item.loose { "\n\n" } else { "\n" };
+            std::iter::once(inline::to_markdown(&item.term, &term_options))
+                .chain(item.definitions.iter().map(|content| {
+                    let body = stmt::indented(content, &indent, "\n\n", options);
+                    if body.is_empty() {
+                        ":".into()
+                    } else {
+                        format!("{}{}", marker, &body[indent.len()..])
+                    }
+                }))
+                .collect::<Vec<_>>()
+                .join(separator)
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::IndentStyle;
+    use crate::syntax::format;
+
+    #[test]
+    fn test_parse_definition_start() {
+        assert_eq!(parse_definition_start(":   text"), Some("text".into()));
+        assert_eq!(parse_definition_start("  :\ttext"), Some("text".into()));
+        assert_eq!(parse_definition_start(":text"), None);
+        assert_eq!(parse_definition_start("    : text"), None);
+    }
+
+    #[test]
+    fn test_to_markdown_normalises_markers() {
+        let input = "Apple\n: A fruit\n  : A company\n\nOrange\n:\tA colour";
+
+        assert_eq!(
+            format(input, &FormatOptions::default()),
+            "Apple\n:   A fruit\n:   A company\n\nOrange\n:   A colour"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_loose_multiple_paragraphs() {
+        let input = "Term\n\n: First line\ncontinued\n\n    Second paragraph\n\n: Other\n\nAfter";
+
+        assert_eq!(
+            format(input, &FormatOptions::default()),
+            "Term\n\n:   First line\n    continued\n\n    Second paragraph\n\n:   Other\n\nAfter"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_indent() {
+        let input = "Term\n: First\n\n    Second";
+        let options = |indent_style, indent_width| FormatOptions {
+            indent_style,
+            indent_width,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            format(input, &options(IndentStyle::Tab, 2)),
+            "Term\n:\tFirst\n\n\tSecond"
+        );
+        assert_eq!(
+            format(input, &options(IndentStyle::Space, 6)),
+            "Term\n:     First\n\n      Second"
+        );
+    }
+}
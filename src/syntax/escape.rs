@@ -0,0 +1,205 @@
+use super::inline::Fragment;
+
+/// Characters whose escapes only matter when they begin a line
+const LINE_START_ONLY: [char; 6] = ['#', '+', '-', '=', '.', ':'];
+
+/// Adjusts the escapes of a single output line. Escapes that only matter at the
+/// start of a line are dropped, and literal text that would start a block
+/// construct there gets escaped again. A line that also starts a line in the
+/// source is only escaped where the source was, since the parser does not
+/// know every block construct and what starts a block there should stay one.
+pub fn normalize(line: &mut Vec<Fragment>, starts_source_line: bool) {
+    let escaped = line
+        .iter()
+        .take_while(|f| **f != Fragment::Space)
+        .any(|f| matches!(f, Fragment::Escape(_)));
+    normalize_cell(line);
+    if !starts_source_line || escaped {
+        escape_line_start(line);
+    }
+}
+
+/// Drops redundant escapes from a table cell, which never starts a line
+pub fn normalize_cell(cell: &mut Vec<Fragment>) {
+    unescape_redundant(cell);
+    merge_text(cell);
+}
+
+fn unescape_redundant(line: &mut [Fragment]) {
+    for i in 0..line.len() {
+        let Fragment::Escape(ch) = line[i] else {
+            continue;
+        };
+        let redundant = match ch {
+            ch if LINE_START_ONLY.contains(&ch) => true,
+            // `\!` only prevents an image when followed by `[`
+            '!' => !line
+                .get(i + 1)
+                .is_some_and(|next| next.to_markdown().starts_with('[')),
+            _ => false,
+        };
+        if redundant {
+            line[i] = Fragment::Text(ch.to_string());
+        }
+    }
+}
+
+fn merge_text(line: &mut Vec<Fragment>) {
+    let mut merged: Vec<Fragment> = Vec::with_capacity(line.len());
+    for fragment in line.drain(..) {
+        match (merged.last_mut(), fragment) {
+            (Some(Fragment::Text(last)), Fragment::Text(text)) => last.push_str(&text),
+            (_, fragment) => merged.push(fragment),
+        }
+    }
+    *line = merged;
+}
+
+fn escape_line_start(line: &mut Vec<Fragment>) {
+    let Some(Fragment::Text(first)) = line.first() else {
+        return;
+    };
+    let line_text = line.iter().map(Fragment::to_markdown).collect::<String>();
+    let Some(idx) = block_start(
+        first,
+        line.len() == 1 || line[1] == Fragment::Space,
+        &line_text,
+    ) else {
+        return;
+    };
+
+    let first = first.clone();
+    let ch = first[idx..].chars().next().unwrap();
+    let mut replacement = vec![];
+    if idx > 0 {
+        replacement.push(Fragment::Text(first[..idx].into()));
+    }
+    replacement.push(Fragment::Escape(ch));
+    if idx + ch.len_utf8() < first.len() {
+        replacement.push(Fragment::Text(first[idx + ch.len_utf8()..].into()));
+    }
+    line.splice(0..1, replacement);
+}
+
+/// Returns the byte index of the character that must be escaped when a line
+/// starting with the word `first` would be read as the start of a block
+fn block_start(first: &str, ends_word: bool, line: &str) -> Option<usize> {
+    let marker = first.chars().next()?;
+    let only = |chars: &str| line.chars().all(|c| c == ' ' || chars.contains(c));
+
+    // ATX headings
+    if ends_word && first.len() <= 6 && first.chars().all(|c| c == '#') {
+        return Some(0);
+    }
+    // Block quotes, code fences and math blocks
+    if marker == '>'
+        || ["```", "~~~", "$$"]
+            .iter()
+            .any(|fence| first.starts_with(fence))
+    {
+        return Some(0);
+    }
+    // Bullet list items and definitions
+    if ends_word && matches!(first, "-" | "+" | "*" | ":") {
+        return Some(0);
+    }
+    // Ordered list items
+    let digits = first.len() - first.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if ends_word && (1..=9).contains(&digits) && matches!(&first[digits..], "." | ")") {
+        return Some(digits);
+    }
+    // Setext underlines and thematic breaks
+    if matches!(marker, '=' | '-') && only(&marker.to_string()) {
+        return Some(0);
+    }
+    if matches!(marker, '*' | '_') && only(&marker.to_string()) && line.matches(marker).count() >= 3
+    {
+        return Some(0);
+    }
+    // Table delimiter rows
+    if only("|-:") && line.contains('|') && line.contains('-') {
+        return Some(0);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::options::{FormatOptions, ProseWrap};
+    use crate::syntax::format;
+
+    /// Fills paragraphs to `line_width`, moving words to line starts
+    fn filled(line_width: usize) -> FormatOptions {
+        FormatOptions {
+            prose_wrap: ProseWrap::Always,
+            line_width,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_escape_heading_at_line_start() {
+        assert_eq!(
+            format("intro # not a heading", &filled(6)),
+            "intro\n\\# not\na\nheading"
+        );
+    }
+
+    #[test]
+    fn test_escape_ordered_list_at_line_start() {
+        assert_eq!(
+            format("in 2023. was a year", &filled(2)),
+            "in\n2023\\.\nwas\na\nyear"
+        );
+    }
+
+    #[test]
+    fn test_escape_list_marker_at_line_start() {
+        assert_eq!(format("a - b +c", &filled(1)), "a\n\\-\nb\n+c");
+        assert_eq!(format("a \\: b", &filled(1)), "a\n\\:\nb");
+    }
+
+    #[test]
+    fn test_keep_source_line_starts() {
+        // Ordered items not starting at 1 cannot interrupt a paragraph
+        let input = "a\n2. x\n: term\n\n- b\n\n> quote";
+        assert_eq!(format(input, &FormatOptions::default()), input);
+        assert_eq!(
+            format("a\\\n\\- b\n1\\. x", &FormatOptions::default()),
+            "a\\\n\\- b\n1\\. x"
+        );
+    }
+
+    #[test]
+    fn test_remove_redundant_escapes() {
+        assert_eq!(
+            format("a \\# b \\- c \\= d", &FormatOptions::default()),
+            "a # b - c = d"
+        );
+        assert_eq!(format("\\#hashtag", &FormatOptions::default()), "#hashtag");
+        assert_eq!(
+            format("\\!bang \\![not image]", &FormatOptions::default()),
+            "!bang \\![not image]"
+        );
+    }
+
+    #[test]
+    fn test_keep_needed_escapes() {
+        assert_eq!(
+            format("\\*not emphasis\\* \\[x\\] \\`", &FormatOptions::default()),
+            "\\*not emphasis\\* \\[x\\] \\`"
+        );
+        assert_eq!(
+            format("x\\\n\\# heading", &FormatOptions::default()),
+            "x\\\n\\# heading"
+        );
+    }
+
+    #[test]
+    fn test_entities_kept_verbatim() {
+        assert_eq!(
+            format("&amp; &#x27; &#39; &copy &", &FormatOptions::default()),
+            "&amp; &#x27; &#39; &copy &"
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use super::inline::Inline;
+use super::stmt::{self, Stmt};
+use crate::options::FormatOptions;
+use std::collections::HashMap;
+
+/// Splits a `[^label]: text` line into its label and the text after the colon
+pub fn parse_definition_start(line: &str) -> Option<(String, String)> {
+    let text = line.trim_start_matches(' ');
+    if line.len() - text.len() > 3 {
+        return None;
+    }
+
+    let rest = text.strip_prefix("[^")?;
+    let end = rest.find("]:")?;
+    let label = &rest[..end];
+    if label.is_empty() || label.contains(|c: char| c.is_whitespace() || c == '[') {
+        return None;
+    }
+    Some((label.into(), rest[end + 2..].trim_start().into()))
+}
+
+/// Returns the width of the leading whitespace of `line`, counting tabs as four
+pub fn indentation(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// Removes up to `columns` of leading whitespace from `line`
+pub fn dedent(line: &str, columns: usize) -> String {
+    let mut width = 0;
+    for (idx, ch) in line.char_indices() {
+        if width >= columns || !(ch == ' ' || ch == '\t') {
+            return line[idx..].into();
+        }
+        width += if ch == '\t' { 4 } else { 1 };
+    }
+    String::new()
+}
+
+/// Converts a footnote definition into Markdown, indenting every block of
+/// its body after the first paragraph.
+pub fn to_markdown(label: &str, content: &[Stmt], options: &FormatOptions) -> String {
+    let marker = format!("[^{}]:", label);
+    let indent = stmt::body_indent(options);
+    let body = stmt::indented(content, &indent, "\n\n", options);
+
+    match content.first() {
+        None => marker,
+        Some(Stmt::Paragraph { .. }) => format!("{} {}", marker, &body[indent.len()..]),
+        Some(_) => format!("{}\n{}", marker, body),
+    }
+}
+
+fn is_numeric(label: &str) -> bool {
+    label.chars().all(|c| c.is_ascii_digit())
+}
+
+/// Renumbers numeric footnotes in the order they are first referenced.
+/// Numeric definitions that are never referenced are numbered after them.
+pub fn renumber(stmts: &mut [Stmt]) {
+    let mut numbers = HashMap::new();
+    let mut assign = |label: &String| {
+        if is_numeric(label) && !numbers.contains_key(label) {
+            numbers.insert(label.clone(), numbers.len() + 1);
+        }
+    };
+
+    for stmt in stmts.iter_mut() {
+        stmt.visit_inlines_mut(&mut |inline| {
+            if let Inline::FootnoteRef(label) = inline {
+                assign(label);
+            }
+        });
+    }
+    for stmt in stmts.iter() {
+        if let Stmt::FootnoteDefinition { label, .. } = stmt {
+            assign(label);
+        }
+    }
+
+    for stmt in stmts.iter_mut() {
+        stmt.visit_inlines_mut(&mut |inline| {
+            if let Inline::FootnoteRef(label) = inline {
+                if let Some(number) = numbers.get(label) {
+                    *label = number.to_string();
+                }
+            }
+        });
+        if let Stmt::FootnoteDefinition { label, .. } = stmt {
+            if let Some(number) = numbers.get(label) {
+                *label = number.to_string();
+            }
+        }
+    }
+}
+
+/// Moves every footnote definition to the end of the document, keeping their
+/// order. Numeric footnotes are sorted by number ahead of named ones when
+/// `sort` is set.
+pub fn move_to_end(stmts: &mut Vec<Stmt>, sort: bool) {
+    let (mut definitions, rest): (Vec<_>, Vec<_>) = stmts
+        .drain(..)
+        .partition(|stmt| matches!(stmt, Stmt::FootnoteDefinition { .. }));
+
+    if sort {
+        definitions.sort_by_key(|stmt| match stmt {
+            Stmt::FootnoteDefinition { label, .. } if is_numeric(label) => {
+                (false, label.parse::<usize>().unwrap_or(usize::MAX))
+            }
+            _ => (true, 0),
+        });
+    }
+
+    stmts.extend(rest);
+    stmts.extend(definitions);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::IndentStyle;
+    use crate::syntax::format;
+
+    fn options(renumber_footnotes: bool, footnotes_at_end: bool) -> FormatOptions {
+        FormatOptions {
+            renumber_footnotes,
+            footnotes_at_end,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_definition_start() {
+        assert_eq!(
+            parse_definition_start("[^note]: Some text"),
+            Some(("note".into(), "Some text".into()))
+        );
+        assert_eq!(parse_definition_start("[^a b]: text"), None);
+        assert_eq!(parse_definition_start("[^]: text"), None);
+        assert_eq!(parse_definition_start("    [^1]: text"), None);
+    }
+
+    #[test]
+    fn test_to_markdown_multiple_paragraphs() {
+        let input = "[^1]: First\n\n\tSecond\n\n    ```\n    code\n    ```";
+
+        assert_eq!(
+            format(input, &options(false, false)),
+            "[^1]: First\n\n    Second\n\n    ```\n    code\n    ```"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_indent() {
+        let input = "[^1]: First\n\n    Second";
+        let options = FormatOptions {
+            indent_style: IndentStyle::Tab,
+            ..Default::default()
+        };
+
+        assert_eq!(format(input, &options), "[^1]: First\n\n\tSecond");
+    }
+
+    #[test]
+    fn test_renumber() {
+        let input =
+            "A[^3] B[^x] C[^1] D[^3]\n\n[^1]: one\n\n[^x]: named\n\n[^3]: three\n\n[^7]: unused";
+
+        assert_eq!(
+            format(input, &options(true, false)),
+            "A[^1] B[^x] C[^2] D[^1]\n\n[^2]: one\n\n[^x]: named\n\n[^1]: three\n\n[^3]: unused"
+        );
+    }
+
+    #[test]
+    fn test_renumber_in_heading() {
+        let input = "# Head[^3] {#head}\n\nText[^1]\n\n[^1]: one\n\n[^3]: three";
+
+        assert_eq!(
+            format(input, &options(true, false)),
+            "# Head[^1] {#head}\n\nText[^2]\n\n[^2]: one\n\n[^1]: three"
+        );
+    }
+
+    #[test]
+    fn test_move_to_end() {
+        let input = "[^b]: bee\n\nA[^2][^b][^1]\n\n[^1]: one\n\n[^2]: two\n\nEnd";
+
+        assert_eq!(
+            format(input, &options(false, true)),
+            "A[^2][^b][^1]\n\nEnd\n\n[^b]: bee\n\n[^1]: one\n\n[^2]: two"
+        );
+        assert_eq!(
+            format(input, &options(true, true)),
+            "A[^1][^b][^2]\n\nEnd\n\n[^1]: two\n\n[^2]: one\n\n[^b]: bee"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::data::{self, toml, yaml};
+use crate::options::FormatOptions;
+
+/// The syntax of a front matter block, identified by its delimiters
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FrontMatterFormat {
+    /// YAML between `---` lines
+    Yaml,
+    /// TOML between `+++` lines
+    Toml,
+    /// A JSON object, either between `;;;` lines or written bare as `{ ... }`
+    Json { fenced: bool },
+}
+
+impl FrontMatterFormat {
+    /// Detects the format from the first line of a document
+    pub fn from_opening(line: &str) -> Option<Self> {
+        match line.trim_end() {
+            "---" => Some(FrontMatterFormat::Yaml),
+            "+++" => Some(FrontMatterFormat::Toml),
+            ";;;" => Some(FrontMatterFormat::Json { fenced: true }),
+            "{" => Some(FrontMatterFormat::Json { fenced: false }),
+            _ => None,
+        }
+    }
+
+    /// The line that ends the block
+    pub fn closing(&self) -> &'static str {
+        match self {
+            FrontMatterFormat::Yaml => "---",
+            FrontMatterFormat::Toml => "+++",
+            FrontMatterFormat::Json { fenced: true } => ";;;",
+            FrontMatterFormat::Json { fenced: false } => "}",
+        }
+    }
+}
+
+/// Converts a front matter block into Markdown. Bare JSON keeps its braces
+/// as part of `content`, other formats are written between their delimiters.
+pub fn to_markdown(format: FrontMatterFormat, content: &str) -> String {
+    match format {
+        FrontMatterFormat::Json { fenced: false } => content.into(),
+        _ => format!("{}\n{}{}", format.closing(), content, format.closing()),
+    }
+}
+
+/// Rewrites YAML or TOML front matter with consistent indentation, quoting
+/// and key order. Returns `None` for JSON and for content outside the
+/// supported subset, which is then left as written.
+pub fn normalize(
+    format: FrontMatterFormat,
+    content: &str,
+    options: &FormatOptions,
+) -> Option<String> {
+    let mut value = match format {
+        FrontMatterFormat::Yaml => yaml::parse(content).ok()?,
+        FrontMatterFormat::Toml => toml::parse(content).ok()?,
+        FrontMatterFormat::Json { .. } => return None,
+    };
+    if let data::Value::Table(entries) = &mut value {
+        data::sort_keys(entries, &options.front_matter_key_order);
+    }
+
+    Some(match format {
+        FrontMatterFormat::Yaml => yaml::write(
+            &value,
+            options.front_matter_indent.max(1),
+            options.front_matter_quote,
+        ),
+        _ => toml::write(&value, options.front_matter_quote),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_yaml() {
+        let options = FormatOptions {
+            front_matter_key_order: vec!["title".into(), "date".into()],
+            ..Default::default()
+        };
+        let content = "tags:\n- a\n-   'b'\ndate: 2024-01-31\ntitle:   \"Hello\"\n\n\n";
+
+        assert_eq!(
+            normalize(FrontMatterFormat::Yaml, content, &options).as_deref(),
+            Some("title: Hello\ndate: 2024-01-31\ntags:\n  - a\n  - b\n")
+        );
+    }
+
+    #[test]
+    fn test_normalize_keeps_unsupported_content() {
+        let options = FormatOptions::default();
+
+        assert_eq!(
+            normalize(FrontMatterFormat::Yaml, "a: 1 # keep me\n", &options),
+            None
+        );
+        assert_eq!(
+            normalize(FrontMatterFormat::Json { fenced: true }, "{}\n", &options),
+            None
+        );
+    }
+}
@@ -0,0 +1,293 @@
+/// Tag names that start an HTML block (CommonMark start condition 6)
+const BLOCK_TAGS: [&str; 62] = [
+    "address",
+    "article",
+    "aside",
+    "base",
+    "basefont",
+    "blockquote",
+    "body",
+    "caption",
+    "center",
+    "col",
+    "colgroup",
+    "dd",
+    "details",
+    "dialog",
+    "dir",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "frame",
+    "frameset",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "head",
+    "header",
+    "hr",
+    "html",
+    "iframe",
+    "legend",
+    "li",
+    "link",
+    "main",
+    "menu",
+    "menuitem",
+    "nav",
+    "noframes",
+    "ol",
+    "optgroup",
+    "option",
+    "p",
+    "param",
+    "search",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "title",
+    "tr",
+    "track",
+    "ul",
+];
+
+/// Tag names whose HTML blocks run until the matching closing tag
+const RAW_TAGS: [&str; 4] = ["pre", "script", "style", "textarea"];
+
+/// The seven ways an HTML block can start, each with its own end condition
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlockKind {
+    Raw,
+    Comment,
+    ProcessingInstruction,
+    Declaration,
+    CData,
+    Block,
+    Tag,
+}
+
+impl BlockKind {
+    /// Checks whether `line` closes an HTML block of this kind. Blocks that
+    /// end at a blank line never end on a line with content.
+    pub fn ends(&self, line: &str) -> bool {
+        let lower = line.to_ascii_lowercase();
+        match self {
+            BlockKind::Raw => RAW_TAGS
+                .iter()
+                .any(|tag| lower.contains(&format!("</{}>", tag))),
+            BlockKind::Comment => line.contains("-->"),
+            BlockKind::ProcessingInstruction => line.contains("?>"),
+            BlockKind::Declaration => line.contains('>'),
+            BlockKind::CData => line.contains("]]>"),
+            BlockKind::Block | BlockKind::Tag => false,
+        }
+    }
+}
+
+/// Returns the kind of HTML block `line` starts, if any
+pub fn block_start(line: &str) -> Option<BlockKind> {
+    let text = line.trim_start_matches(' ');
+    if line.len() - text.len() > 3 || !text.starts_with('<') {
+        return None;
+    }
+
+    let lower = text.to_ascii_lowercase();
+    let tag_end = |name: &str, offset: usize| {
+        let after = &lower[offset + name.len()..];
+        after.is_empty() || after.starts_with(|c: char| c.is_whitespace() || c == '>')
+    };
+
+    if RAW_TAGS
+        .iter()
+        .any(|tag| lower[1..].starts_with(tag) && tag_end(tag, 1))
+    {
+        return Some(BlockKind::Raw);
+    }
+    if text.starts_with("<!--") {
+        return Some(BlockKind::Comment);
+    }
+    if text.starts_with("<?") {
+        return Some(BlockKind::ProcessingInstruction);
+    }
+    if text.starts_with("<![CDATA[") {
+        return Some(BlockKind::CData);
+    }
+    if text.starts_with("<!") && text[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Some(BlockKind::Declaration);
+    }
+
+    let offset = if lower.starts_with("</") { 2 } else { 1 };
+    if BLOCK_TAGS.iter().any(|tag| {
+        lower[offset..].starts_with(tag)
+            && (tag_end(tag, offset) || lower[offset + tag.len()..].starts_with("/>"))
+    }) {
+        return Some(BlockKind::Block);
+    }
+
+    let tag = tag_len(text)?;
+    let name = lower[offset..].split(|c: char| !c.is_ascii_alphanumeric() && c != '-');
+    if text[tag..].trim().is_empty() && !name.take(1).any(|n| RAW_TAGS.contains(&n)) {
+        return Some(BlockKind::Tag);
+    }
+    None
+}
+
+/// Returns the length of the raw HTML at the start of `text`: a tag, comment,
+/// processing instruction, declaration or CDATA section
+pub fn inline_len(text: &str) -> Option<usize> {
+    let delimited = |open: &str, close: &str| {
+        let body = text.strip_prefix(open)?;
+        body.find(close).map(|end| open.len() + end + close.len())
+    };
+
+    if text.starts_with("<!--") {
+        // `<!-->` and `<!--->` are complete comments too
+        ["<!-->", "<!--->"]
+            .iter()
+            .find(|empty| text.starts_with(*empty))
+            .map(|empty| empty.len())
+            .or_else(|| delimited("<!--", "-->"))
+    } else if text.starts_with("<?") {
+        delimited("<?", "?>")
+    } else if text.starts_with("<![CDATA[") {
+        delimited("<![CDATA[", "]]>")
+    } else if text.starts_with("<!") && text[2..].starts_with(|c: char| c.is_ascii_alphabetic()) {
+        delimited("<!", ">")
+    } else {
+        tag_len(text)
+    }
+}
+
+/// Returns the length of the open or closing tag at the start of `text`
+pub fn tag_len(text: &str) -> Option<usize> {
+    let mut scanner = Scanner { text, pos: 0 };
+    scanner.eat("<")?;
+    let closing = scanner.eat("/").is_some();
+    scanner.name(
+        |c| c.is_ascii_alphabetic(),
+        |c| c.is_ascii_alphanumeric() || c == '-',
+    )?;
+
+    if closing {
+        scanner.whitespace();
+        scanner.eat(">")?;
+        return Some(scanner.pos);
+    }
+
+    loop {
+        let before = scanner.pos;
+        if scanner.whitespace() == 0 {
+            break;
+        }
+        let is_start = |c: char| c.is_ascii_alphabetic() || c == '_' || c == ':';
+        let is_rest = |c: char| c.is_ascii_alphanumeric() || "_.:-".contains(c);
+        if scanner.name(is_start, is_rest).is_none() {
+            scanner.pos = before;
+            break;
+        }
+        let value_start = scanner.pos;
+        scanner.whitespace();
+        if scanner.eat("=").is_some() {
+            scanner.whitespace();
+            scanner.attribute_value()?;
+        } else {
+            scanner.pos = value_start;
+        }
+    }
+
+    scanner.whitespace();
+    scanner.eat("/");
+    scanner.eat(">")?;
+    Some(scanner.pos)
+}
+
+struct Scanner<'s> {
+    text: &'s str,
+    pos: usize,
+}
+
+impl Scanner<'_> {
+    fn rest(&self) -> &str {
+        &self.text[self.pos..]
+    }
+
+    fn eat(&mut self, prefix: &str) -> Option<()> {
+        self.rest()
+            .starts_with(prefix)
+            .then(|| self.pos += prefix.len())
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> usize {
+        let len = self.rest().len() - self.rest().trim_start_matches(pred).len();
+        self.pos += len;
+        len
+    }
+
+    fn whitespace(&mut self) -> usize {
+        self.take_while(|c| c == ' ' || c == '\t' || c == '\n')
+    }
+
+    fn name(&mut self, start: impl Fn(char) -> bool, rest: impl Fn(char) -> bool) -> Option<()> {
+        self.rest().starts_with(start).then_some(())?;
+        self.pos += 1;
+        self.take_while(rest);
+        Some(())
+    }
+
+    fn attribute_value(&mut self) -> Option<()> {
+        for quote in ['"', '\''] {
+            if self.rest().starts_with(quote) {
+                let end = self.rest()[1..].find(quote)?;
+                self.pos += end + 2;
+                return Some(());
+            }
+        }
+        let unquoted = self.take_while(|c| !c.is_whitespace() && !"\"'=<>`".contains(c));
+        (unquoted > 0).then_some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_block_start_conditions() {
+        assert_eq!(block_start("<pre class=\"x\">"), Some(BlockKind::Raw));
+        assert_eq!(block_start("<!-- note -->"), Some(BlockKind::Comment));
+        assert_eq!(block_start("<?php"), Some(BlockKind::ProcessingInstruction));
+        assert_eq!(block_start("<!DOCTYPE html>"), Some(BlockKind::Declaration));
+        assert_eq!(block_start("<![CDATA["), Some(BlockKind::CData));
+        assert_eq!(block_start("<details>"), Some(BlockKind::Block));
+        assert_eq!(block_start("  </div>"), Some(BlockKind::Block));
+        assert_eq!(
+            block_start("<img width=100 src=\"a.png\" />"),
+            Some(BlockKind::Tag)
+        );
+        assert_eq!(block_start("<img src=a.png> text"), None);
+        assert_eq!(block_start("<divider>"), Some(BlockKind::Tag));
+        assert_eq!(block_start("    <div>"), None);
+    }
+
+    #[test]
+    fn test_inline_len() {
+        assert_eq!(inline_len("<img width=\"100\"  src='a b'>x"), Some(28));
+        assert_eq!(inline_len("</a >"), Some(5));
+        assert_eq!(inline_len("<!-- c -->"), Some(10));
+        assert_eq!(inline_len("<a href=>"), None);
+        assert_eq!(inline_len("<3 you"), None);
+    }
+}
@@ -0,0 +1,68 @@
+use super::stmt::Stmt;
+
+/// An HTML comment that keeps part of a document from being formatted
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Directive {
+    /// `<!-- mdf-ignore -->`, for the next block
+    Next,
+    /// `<!-- mdf-ignore-start -->`, up to the matching end directive
+    Start,
+    /// `<!-- mdf-ignore-end -->`
+    End,
+    /// `<!-- mdf-ignore-file -->`, for the whole document
+    File,
+}
+
+/// Recognises a line holding only an ignore directive
+pub fn directive(line: &str) -> Option<Directive> {
+    let name = line
+        .trim()
+        .strip_prefix("<!--")?
+        .strip_suffix("-->")?
+        .trim();
+    match name {
+        "mdf-ignore" => Some(Directive::Next),
+        "mdf-ignore-start" => Some(Directive::Start),
+        "mdf-ignore-end" => Some(Directive::End),
+        "mdf-ignore-file" => Some(Directive::File),
+        _ => None,
+    }
+}
+
+/// Checks whether a document opts out of formatting altogether, with a
+/// directive of its own outside code blocks
+pub fn ignores_file(stmts: &[Stmt]) -> bool {
+    stmts.iter().any(|stmt| match stmt {
+        Stmt::HtmlBlock { content } => directive(content) == Some(Directive::File),
+        _ => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::lexer::Lexer;
+    use crate::syntax::parser::Parser;
+
+    #[test]
+    fn test_directive() {
+        assert_eq!(directive("<!-- mdf-ignore -->"), Some(Directive::Next));
+        assert_eq!(
+            directive("  <!--mdf-ignore-start-->\r"),
+            Some(Directive::Start)
+        );
+        assert_eq!(directive("<!-- mdf-ignore-end -->"), Some(Directive::End));
+        assert_eq!(directive("<!-- mdf-ignore --> text"), None);
+        assert_eq!(directive("<!-- prettier-ignore -->"), None);
+    }
+
+    #[test]
+    fn test_ignores_file() {
+        let ignores_file =
+            |input: &str| ignores_file(&Parser::new(input, Lexer::new(input)).parse().unwrap());
+        assert!(ignores_file("# Title\n\n<!-- mdf-ignore-file -->\n"));
+        assert!(!ignores_file(
+            "# Title\n\n```md\n<!-- mdf-ignore-file -->\n```\n"
+        ));
+    }
+}
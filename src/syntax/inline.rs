@@ -0,0 +1,1016 @@
+use super::{escape, html, wrap};
+use crate::options::FormatOptions;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Inline {
+    Text(String),
+    Space,
+    /// A line ending inside a paragraph
+    SoftBreak,
+    HardBreak,
+    /// A backslash-escaped ASCII punctuation character
+    Escape(char),
+    /// An entity or numeric character reference, kept verbatim
+    Entity(String),
+    Code(String),
+    /// Raw inline HTML, kept verbatim
+    Html(String),
+    /// Inline math including its delimiters, never reflowed or escaped
+    Math(String),
+    Strikethrough(Vec<Inline>),
+    FootnoteRef(String),
+    Link {
+        image: bool,
+        text: Vec<Inline>,
+        destination: String,
+    },
+    Autolink {
+        target: String,
+        bracketed: bool,
+    },
+    /// A `[[page#heading|alias]]` wiki link, or `![[...]]` embed
+    WikiLink {
+        embed: bool,
+        page: String,
+        heading: Option<String>,
+        alias: Option<String>,
+    },
+}
+
+/// A piece of rendered inline content, split out so that line layout and
+/// escaping can work on the flat sequence rather than the nested `Inline`s
+#[derive(Clone, Debug, PartialEq)]
+pub enum Fragment {
+    /// Literal text that may need escaping
+    Text(String),
+    Escape(char),
+    /// Markup written as-is
+    Raw(String),
+    Space,
+    SoftBreak,
+    HardBreak,
+}
+
+impl Fragment {
+    pub fn to_markdown(&self) -> String {
+        match self {
+            Fragment::Text(text) | Fragment::Raw(text) => text.clone(),
+            Fragment::Escape(ch) => format!("\\{}", ch),
+            Fragment::Space => " ".into(),
+            Fragment::SoftBreak | Fragment::HardBreak => "\n".into(),
+        }
+    }
+}
+
+impl Inline {
+    /// Calls `f` on this `Inline` and every `Inline` nested inside it.
+    pub fn visit_mut<F: FnMut(&mut Inline)>(&mut self, f: &mut F) {
+        f(self);
+        match self {
+            Inline::Strikethrough(children) | Inline::Link { text: children, .. } => {
+                children.iter_mut().for_each(|child| child.visit_mut(f));
+            }
+            _ => {}
+        }
+    }
+
+    /// Flattens an `Inline` into the fragments it is written as.
+    pub fn fragments(&self, options: &FormatOptions, out: &mut Vec<Fragment>) {
+        match self {
+            Inline::Text(text) => out.push(Fragment::Text(text.clone())),
+            Inline::Space => out.push(Fragment::Space),
+            Inline::SoftBreak => out.push(Fragment::SoftBreak),
+            Inline::HardBreak => out.push(Fragment::HardBreak),
+            Inline::Escape(ch) => out.push(Fragment::Escape(*ch)),
+            Inline::Entity(raw) | Inline::Code(raw) | Inline::Html(raw) | Inline::Math(raw) => {
+                out.push(Fragment::Raw(raw.clone()))
+            }
+            Inline::FootnoteRef(label) => out.push(Fragment::Raw(format!("[^{}]", label))),
+            Inline::Strikethrough(children) => {
+                let delimiter = options.strikethrough_delimiter.as_str();
+                out.push(Fragment::Raw(delimiter.into()));
+                children.iter().for_each(|c| c.fragments(options, out));
+                out.push(Fragment::Raw(delimiter.into()));
+            }
+            Inline::Link {
+                image,
+                text,
+                destination,
+            } => {
+                out.push(Fragment::Raw(if *image { "![" } else { "[" }.into()));
+                text.iter().for_each(|c| c.fragments(options, out));
+                out.push(Fragment::Raw(format!("]({})", destination)));
+            }
+            Inline::WikiLink {
+                embed,
+                page,
+                heading,
+                alias,
+            } => {
+                let mut link = format!("{}[[{}", if *embed { "!" } else { "" }, page);
+                if let Some(heading) = heading {
+                    link.push('#');
+                    link.push_str(heading);
+                }
+                if let Some(alias) = alias {
+                    link.push('|');
+                    link.push_str(alias);
+                }
+                link.push_str("]]");
+                out.push(Fragment::Raw(link));
+            }
+            Inline::Autolink { target, bracketed } => {
+                if *bracketed || (options.wrap_bare_urls && has_scheme(target)) {
+                    out.push(Fragment::Raw(format!("<{}>", target)));
+                } else {
+                    out.push(Fragment::Raw(target.clone()));
+                }
+            }
+        }
+    }
+}
+
+/// Splits a sequence of `Inline`s into output lines according to the prose
+/// wrap mode, with escapes adjusted for where each line starts. Every line
+/// but the last ends with its break fragment.
+pub fn lines(inlines: &[Inline], options: &FormatOptions) -> Vec<Vec<Fragment>> {
+    let mut fragments = vec![];
+    inlines
+        .iter()
+        .for_each(|i| i.fragments(options, &mut fragments));
+
+    // Whether each fragment other than spaces and breaks starts a line in
+    // the source. Wrapping keeps these fragments in order.
+    let mut source_line_starts = vec![];
+    let mut at_line_start = true;
+    for fragment in &fragments {
+        match fragment {
+            Fragment::SoftBreak | Fragment::HardBreak => at_line_start = true,
+            Fragment::Space => at_line_start = false,
+            _ => {
+                source_line_starts.push(at_line_start);
+                at_line_start = false;
+            }
+        }
+    }
+
+    let mut lines = wrap::layout(fragments, options);
+    let mut content_fragments = 0;
+    for line in lines.iter_mut() {
+        let end = line.pop_if(|f| matches!(f, Fragment::SoftBreak | Fragment::HardBreak));
+        let starts_source_line = source_line_starts
+            .get(content_fragments)
+            .copied()
+            .unwrap_or(true);
+        content_fragments += line
+            .iter()
+            .filter(|f| !matches!(f, Fragment::Space))
+            .count();
+        escape::normalize(line, starts_source_line);
+        line.extend(end);
+    }
+    lines
+}
+
+/// Converts a sequence of `Inline`s into a Markdown string.
+pub fn to_markdown(inlines: &[Inline], options: &FormatOptions) -> String {
+    lines(inlines, options)
+        .iter()
+        .flatten()
+        .map(|fragment| match fragment {
+            Fragment::HardBreak => options.hard_break.as_str().into(),
+            fragment => fragment.to_markdown(),
+        })
+        .collect()
+}
+
+/// Converts the `Inline`s of a table cell into a Markdown string.
+pub fn cell_to_markdown(inlines: &[Inline], options: &FormatOptions) -> String {
+    let mut fragments = vec![];
+    inlines
+        .iter()
+        .for_each(|i| i.fragments(options, &mut fragments));
+    escape::normalize_cell(&mut fragments);
+    fragments.iter().map(Fragment::to_markdown).collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct InlineParser<'s> {
+    source: &'s str,
+    pos: usize,
+    /// Length of the tilde run that ends a nested strikethrough
+    closer: Option<usize>,
+    closed: bool,
+    /// Parse `[[wiki links]]` and `![[embeds]]`
+    wiki_links: bool,
+}
+
+impl<'s> InlineParser<'s> {
+    pub fn new(source: &'s str) -> Self {
+        Self {
+            source,
+            pos: 0,
+            closer: None,
+            closed: false,
+            wiki_links: false,
+        }
+    }
+
+    pub fn wiki_links(mut self, enabled: bool) -> Self {
+        self.wiki_links = enabled;
+        self
+    }
+
+    pub fn parse(&mut self) -> Vec<Inline> {
+        let mut inlines = vec![];
+
+        while let Some(ch) = self.peek() {
+            match ch {
+                ' ' | '\t' | '\n' => {
+                    let whitespace = self.consume_while(|c| c == ' ' || c == '\t' || c == '\n');
+                    if !inlines.is_empty() && self.peek().is_some() {
+                        // Two or more spaces before a line ending make a hard break
+                        let hard = whitespace
+                            .split_once('\n')
+                            .is_some_and(|(before, _)| before.ends_with("  "));
+                        inlines.push(if hard {
+                            Inline::HardBreak
+                        } else if whitespace.contains('\n') {
+                            Inline::SoftBreak
+                        } else {
+                            Inline::Space
+                        });
+                    }
+                }
+                '\\' if self.rest()[1..].starts_with(|c: char| c.is_ascii_punctuation()) => {
+                    self.pos += 1;
+                    let escaped = self.peek().unwrap();
+                    self.pos += 1;
+                    inlines.push(Inline::Escape(escaped));
+                }
+                '&' => match self.parse_entity() {
+                    Some(entity) => inlines.push(entity),
+                    None => self.push_char(&mut inlines),
+                },
+                '\\' if self.rest().starts_with("\\\n") => {
+                    self.pos += 1;
+                    self.consume_while(|c| c == ' ' || c == '\t' || c == '\n');
+                    if self.peek().is_some() {
+                        inlines.push(Inline::HardBreak);
+                    } else {
+                        push_text(&mut inlines, "\\");
+                    }
+                }
+                '`' => match self.parse_code_span() {
+                    Some(code) => inlines.push(code),
+                    None => {
+                        let ticks = self.consume_while(|c| c == '`');
+                        push_text(&mut inlines, ticks);
+                    }
+                },
+                '$' => match self.parse_math() {
+                    Some(math) => inlines.push(math),
+                    None => {
+                        let dollars = self.consume_while(|c| c == '$');
+                        push_text(&mut inlines, dollars);
+                    }
+                },
+                '~' => {
+                    let run = tilde_run(self.rest());
+                    if self.closer == Some(run) && !self.after_whitespace() {
+                        self.closed = true;
+                        break;
+                    }
+                    match self.parse_strikethrough(run) {
+                        Some(strikethrough) => inlines.push(strikethrough),
+                        None => {
+                            let tildes = self.consume_while(|c| c == '~');
+                            push_text(&mut inlines, tildes);
+                        }
+                    }
+                }
+                '<' => match self.parse_autolink().or_else(|| self.parse_html()) {
+                    Some(inline) => inlines.push(inline),
+                    None => self.push_char(&mut inlines),
+                },
+                '!' if self.wiki_links && self.rest().starts_with("![[") => {
+                    match self.parse_wiki_link(true) {
+                        Some(embed) => inlines.push(embed),
+                        None => self.push_char(&mut inlines),
+                    }
+                }
+                '[' if self.wiki_links && self.rest().starts_with("[[") => {
+                    match self
+                        .parse_wiki_link(false)
+                        .or_else(|| self.parse_link(false))
+                    {
+                        Some(link) => inlines.push(link),
+                        None => self.push_char(&mut inlines),
+                    }
+                }
+                '!' if self.rest().starts_with("![") => match self.parse_link(true) {
+                    Some(image) => inlines.push(image),
+                    None => self.push_char(&mut inlines),
+                },
+                '[' if self.rest().starts_with("[^") => match self.parse_footnote_ref() {
+                    Some(footnote) => inlines.push(footnote),
+                    None => self.push_char(&mut inlines),
+                },
+                '[' => match self.parse_link(false) {
+                    Some(link) => inlines.push(link),
+                    None => self.push_char(&mut inlines),
+                },
+                'w' | 'W' | 'h' | 'H' if self.at_autolink_boundary() => {
+                    match self.parse_extended_autolink() {
+                        Some(autolink) => inlines.push(autolink),
+                        None => self.push_char(&mut inlines),
+                    }
+                }
+                _ => self.push_char(&mut inlines),
+            }
+        }
+
+        inlines
+    }
+
+    /// Parses a code span, leaving the backtick runs in the raw text
+    fn parse_code_span(&mut self) -> Option<Inline> {
+        let rest = self.rest();
+        let ticks = backtick_run(rest);
+
+        let mut search = ticks;
+        while let Some(offset) = rest[search..].find('`') {
+            let start = search + offset;
+            let run = backtick_run(&rest[start..]);
+            if run == ticks {
+                let end = start + run;
+                self.pos += end;
+                return Some(Inline::Code(rest[..end].replace('\n', " ")));
+            }
+            search = start + run;
+        }
+        None
+    }
+
+    /// Parses `&name;`, `&#123;` and `&#x1F;` character references
+    fn parse_entity(&mut self) -> Option<Inline> {
+        let rest = self.rest();
+        let end = rest.find(';')?;
+        let body = &rest[1..end];
+        let valid = if let Some(hex) = body.strip_prefix("#x").or(body.strip_prefix("#X")) {
+            (1..=6).contains(&hex.len()) && hex.chars().all(|c| c.is_ascii_hexdigit())
+        } else if let Some(decimal) = body.strip_prefix('#') {
+            (1..=7).contains(&decimal.len()) && decimal.chars().all(|c| c.is_ascii_digit())
+        } else {
+            (2..=32).contains(&body.len())
+                && body.starts_with(|c: char| c.is_ascii_alphabetic())
+                && body.chars().all(|c| c.is_ascii_alphanumeric())
+        };
+        if !valid {
+            return None;
+        }
+
+        self.pos += end + 1;
+        Some(Inline::Entity(rest[..=end].into()))
+    }
+
+    /// Parses `$...$`, `$$...$$` and `` $`...`$ `` inline math
+    fn parse_math(&mut self) -> Option<Inline> {
+        let rest = self.rest();
+        let end = if let Some(body) = rest.strip_prefix("$`") {
+            2 + body.find("`$")? + 2
+        } else if let Some(body) = rest.strip_prefix("$$") {
+            2 + body.find("$$").filter(|&end| end > 0)? + 2
+        } else {
+            // `$` must hug its content, and a closing `$` before a digit is a price
+            let body = &rest[1..];
+            if body.starts_with(|c: char| c.is_whitespace() || c == '$') {
+                return None;
+            }
+            let mut escaped = false;
+            let close = body.char_indices().find(|&(idx, ch)| {
+                let found = ch == '$'
+                    && !escaped
+                    && !body[..idx].ends_with(char::is_whitespace)
+                    && !body[idx + 1..].starts_with(|c: char| c.is_ascii_digit());
+                escaped = ch == '\\' && !escaped;
+                found
+            });
+            1 + close?.0 + 1
+        };
+
+        self.pos += end;
+        Some(Inline::Math(rest[..end].replace('\n', " ")))
+    }
+
+    /// Parses `~text~` and `~~text~~`, requiring matching delimiter runs
+    fn parse_strikethrough(&mut self, run: usize) -> Option<Inline> {
+        let rest = self.rest();
+        let inner = &rest[run..];
+        if run > 2 || inner.is_empty() || inner.starts_with(char::is_whitespace) {
+            return None;
+        }
+
+        let mut parser = InlineParser::new(inner).wiki_links(self.wiki_links);
+        parser.closer = Some(run);
+        let children = parser.parse();
+        if !parser.closed || children.is_empty() {
+            return None;
+        }
+
+        self.pos += run + parser.pos + run;
+        Some(Inline::Strikethrough(children))
+    }
+
+    /// Parses `<scheme:...>` and `<user@example.com>` autolinks
+    fn parse_autolink(&mut self) -> Option<Inline> {
+        let rest = self.rest();
+        let end = rest.find('>')?;
+        let target = &rest[1..end];
+        if !is_uri(target) && !is_email(target) {
+            return None;
+        }
+
+        self.pos += end + 1;
+        Some(Inline::Autolink {
+            target: target.into(),
+            bracketed: true,
+        })
+    }
+
+    /// Parses raw HTML tags, comments and declarations
+    fn parse_html(&mut self) -> Option<Inline> {
+        let rest = self.rest();
+        let len = html::inline_len(rest)?;
+        self.pos += len;
+        Some(Inline::Html(rest[..len].into()))
+    }
+
+    /// Parses GFM extended `www.`, `http://` and `https://` autolinks
+    fn parse_extended_autolink(&mut self) -> Option<Inline> {
+        let rest = self.rest();
+        let domain_start = ["www.", "http://", "https://"]
+            .iter()
+            .find(|prefix| starts_with_ignore_case(rest, prefix))
+            .map(|prefix| if *prefix == "www." { 0 } else { prefix.len() })?;
+
+        let end = rest
+            .find(|c: char| c.is_whitespace() || c == '<')
+            .unwrap_or(rest.len());
+        let target = trim_autolink_end(&rest[..end]);
+        if target.len() <= domain_start {
+            return None;
+        }
+
+        let after = &target[domain_start..];
+        let domain_end = after
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+            .unwrap_or(after.len());
+        if !is_valid_domain(&after[..domain_end]) {
+            return None;
+        }
+
+        self.pos += target.len();
+        Some(Inline::Autolink {
+            target: target.into(),
+            bracketed: false,
+        })
+    }
+
+    /// Parses `[^label]` footnote references
+    fn parse_footnote_ref(&mut self) -> Option<Inline> {
+        let rest = self.rest();
+        let end = rest.find(']')?;
+        let label = &rest[2..end];
+        if label.is_empty() || label.contains(|c: char| c.is_whitespace() || c == '[') {
+            return None;
+        }
+
+        self.pos += end + 1;
+        Some(Inline::FootnoteRef(label.into()))
+    }
+
+    /// Parses `[[page#heading|alias]]` wiki links and `![[file]]` embeds
+    fn parse_wiki_link(&mut self, embed: bool) -> Option<Inline> {
+        let rest = self.rest();
+        let open = if embed { 3 } else { 2 };
+        let end = open + rest[open..].find("]]")?;
+        let inner = &rest[open..end];
+        if inner.trim().is_empty() || inner.contains(['[', ']', '\n']) {
+            return None;
+        }
+
+        // Inside tables the alias pipe is written `\|`, so the backslash
+        // stays on the target to round-trip
+        let (target, alias) = match inner.split_once('|') {
+            Some((target, alias)) => (target, Some(alias.trim().into())),
+            None => (inner, None),
+        };
+        let (page, heading) = match target.split_once('#') {
+            Some((page, heading)) => (page, Some(heading.trim().into())),
+            None => (target, None),
+        };
+
+        self.pos += end + 2;
+        Some(Inline::WikiLink {
+            embed,
+            page: page.trim().into(),
+            heading,
+            alias,
+        })
+    }
+
+    /// Parses `[text](destination)` links and `![alt](source)` images
+    fn parse_link(&mut self, image: bool) -> Option<Inline> {
+        let rest = self.rest();
+        let open = if image { 2 } else { 1 };
+        let close = open + find_closing(&rest[open..], '[', ']')?;
+
+        let after = &rest[close + 1..];
+        if !after.starts_with('(') {
+            return None;
+        }
+        let end = 1 + find_closing(&after[1..], '(', ')')?;
+
+        let text = InlineParser::new(&rest[open..close])
+            .wiki_links(self.wiki_links)
+            .parse();
+        let destination = after[1..end].replace('\n', " ");
+        self.pos += close + 1 + end + 1;
+        Some(Inline::Link {
+            image,
+            text,
+            destination,
+        })
+    }
+
+    fn after_whitespace(&self) -> bool {
+        self.source[..self.pos]
+            .chars()
+            .next_back()
+            .is_none_or(char::is_whitespace)
+    }
+
+    fn at_autolink_boundary(&self) -> bool {
+        match self.source[..self.pos].chars().next_back() {
+            None => true,
+            Some(prev) => prev.is_whitespace() || matches!(prev, '*' | '_' | '~' | '('),
+        }
+    }
+
+    fn push_char(&mut self, inlines: &mut Vec<Inline>) {
+        if let Some(ch) = self.peek() {
+            self.pos += ch.len_utf8();
+            push_text(inlines, &self.source[self.pos - ch.len_utf8()..self.pos]);
+        }
+    }
+
+    fn consume_while<P>(&mut self, mut pred: P) -> &'s str
+    where
+        P: FnMut(char) -> bool,
+    {
+        let start = self.pos;
+        while let Some(ch) = self.peek() {
+            if !pred(ch) {
+                break;
+            }
+            self.pos += ch.len_utf8();
+        }
+        &self.source[start..self.pos]
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.source[self.pos..]
+    }
+}
+
+fn push_text(inlines: &mut Vec<Inline>, text: &str) {
+    if let Some(Inline::Text(last)) = inlines.last_mut() {
+        last.push_str(text);
+    } else {
+        inlines.push(Inline::Text(text.into()));
+    }
+}
+
+fn backtick_run(text: &str) -> usize {
+    text.len() - text.trim_start_matches('`').len()
+}
+
+fn tilde_run(text: &str) -> usize {
+    text.len() - text.trim_start_matches('~').len()
+}
+
+/// Finds the byte index of the unescaped `close` that balances an already consumed `open`
+fn find_closing(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (idx, ch) in text.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == open {
+            depth += 1;
+        } else if ch == close {
+            if depth == 0 {
+                return Some(idx);
+            }
+            depth -= 1;
+        }
+    }
+    None
+}
+
+/// Removes trailing punctuation that GFM excludes from extended autolinks
+fn trim_autolink_end(mut text: &str) -> &str {
+    loop {
+        if let Some(trimmed) = text.strip_suffix(['?', '!', '.', ',', ':', '*', '_', '~']) {
+            text = trimmed;
+        } else if text.ends_with(')') && text.matches(')').count() > text.matches('(').count() {
+            text = &text[..text.len() - 1];
+        } else if let Some(amp) = entity_suffix(text) {
+            text = &text[..amp];
+        } else {
+            return text;
+        }
+    }
+}
+
+/// Returns the start of a trailing entity-like `&name;` sequence
+fn entity_suffix(text: &str) -> Option<usize> {
+    let body = text.strip_suffix(';')?;
+    let amp = body.rfind('&')?;
+    let name = &body[amp + 1..];
+    (!name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric())).then_some(amp)
+}
+
+fn starts_with_ignore_case(text: &str, prefix: &str) -> bool {
+    text.get(..prefix.len())
+        .is_some_and(|start| start.eq_ignore_ascii_case(prefix))
+}
+
+fn has_scheme(target: &str) -> bool {
+    starts_with_ignore_case(target, "http://") || starts_with_ignore_case(target, "https://")
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    let segments = domain.split('.').collect::<Vec<_>>();
+    segments.len() >= 2
+        && segments.iter().all(|s| !s.is_empty())
+        && segments.iter().rev().take(2).all(|s| !s.contains('_'))
+}
+
+fn is_uri(target: &str) -> bool {
+    let Some((scheme, rest)) = target.split_once(':') else {
+        return false;
+    };
+    (2..=32).contains(&scheme.len())
+        && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '.' | '-'))
+        && rest
+            .chars()
+            .all(|c| !c.is_whitespace() && !c.is_control() && c != '<' && c != '>')
+}
+
+fn is_email(target: &str) -> bool {
+    let Some((local, domain)) = target.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ".!#$%&'*+/=?^_`{|}~-".contains(c))
+        && domain.split('.').all(|label| {
+            (1..=63).contains(&label.len())
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{HardBreakStyle, StrikethroughDelimiter};
+
+    fn autolink(target: &str, bracketed: bool) -> Inline {
+        Inline::Autolink {
+            target: target.into(),
+            bracketed,
+        }
+    }
+
+    #[test]
+    fn test_autolink_uri() {
+        let inlines = InlineParser::new("see <https://example.com/a?b=c>").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Text("see".into()),
+                Inline::Space,
+                autolink("https://example.com/a?b=c", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_autolink_email() {
+        let inlines = InlineParser::new("<user@example.com>").parse();
+
+        assert_eq!(inlines, vec![autolink("user@example.com", true)]);
+    }
+
+    #[test]
+    fn test_autolink_invalid() {
+        let inlines = InlineParser::new("<example.com>").parse();
+
+        assert_eq!(inlines, vec![Inline::Text("<example.com>".into())]);
+    }
+
+    #[test]
+    fn test_extended_autolink_trailing_punctuation() {
+        let inlines = InlineParser::new("Visit www.example.com/docs.").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Text("Visit".into()),
+                Inline::Space,
+                autolink("www.example.com/docs", false),
+                Inline::Text(".".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extended_autolink_parentheses() {
+        let inlines = InlineParser::new("(https://en.wikipedia.org/wiki/Rust_(language))").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Text("(".into()),
+                autolink("https://en.wikipedia.org/wiki/Rust_(language)", false),
+                Inline::Text(")".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extended_autolink_requires_boundary() {
+        let inlines = InlineParser::new("awww.example.com").parse();
+
+        assert_eq!(inlines, vec![Inline::Text("awww.example.com".into())]);
+    }
+
+    #[test]
+    fn test_wrap_bare_urls() {
+        let options = FormatOptions {
+            wrap_bare_urls: true,
+            ..Default::default()
+        };
+        let inlines =
+            InlineParser::new("https://example.com, www.example.com and `https://code.rs`").parse();
+
+        assert_eq!(
+            to_markdown(&inlines, &options),
+            "<https://example.com>, www.example.com and `https://code.rs`"
+        );
+    }
+
+    #[test]
+    fn test_link_destination_not_wrapped() {
+        let options = FormatOptions {
+            wrap_bare_urls: true,
+            ..Default::default()
+        };
+        let inlines = InlineParser::new("[docs](https://example.com)").parse();
+
+        assert_eq!(
+            to_markdown(&inlines, &options),
+            "[docs](https://example.com)"
+        );
+    }
+
+    #[test]
+    fn test_strikethrough_multiple_words() {
+        let inlines = InlineParser::new("~~old and busted~~ new").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Strikethrough(vec![
+                    Inline::Text("old".into()),
+                    Inline::Space,
+                    Inline::Text("and".into()),
+                    Inline::Space,
+                    Inline::Text("busted".into()),
+                ]),
+                Inline::Space,
+                Inline::Text("new".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strikethrough_mismatched_delimiters() {
+        let inlines = InlineParser::new("~~a~ and ~~~b~~~ ~~ c").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Text("~~a~".into()),
+                Inline::Space,
+                Inline::Text("and".into()),
+                Inline::Space,
+                Inline::Text("~~~b~~~".into()),
+                Inline::Space,
+                Inline::Text("~~".into()),
+                Inline::Space,
+                Inline::Text("c".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_strikethrough_normalised_delimiter() {
+        let inlines = InlineParser::new("~one~ and ~~two~~").parse();
+
+        assert_eq!(
+            to_markdown(&inlines, &FormatOptions::default()),
+            "~~one~~ and ~~two~~"
+        );
+
+        let options = FormatOptions {
+            strikethrough_delimiter: StrikethroughDelimiter::Single,
+            ..Default::default()
+        };
+        assert_eq!(to_markdown(&inlines, &options), "~one~ and ~two~");
+    }
+
+    #[test]
+    fn test_hard_breaks() {
+        let inlines = InlineParser::new("Jane Doe  \n1 Main St\\\nSpringfield").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Text("Jane".into()),
+                Inline::Space,
+                Inline::Text("Doe".into()),
+                Inline::HardBreak,
+                Inline::Text("1".into()),
+                Inline::Space,
+                Inline::Text("Main".into()),
+                Inline::Space,
+                Inline::Text("St".into()),
+                Inline::HardBreak,
+                Inline::Text("Springfield".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hard_break_at_end_is_not_a_break() {
+        let inlines = InlineParser::new("one  \ntwo\\\n").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Text("one".into()),
+                Inline::HardBreak,
+                Inline::Text("two\\".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_hard_break_style() {
+        let inlines = InlineParser::new("roses\\\nviolets  \nsugar").parse();
+
+        assert_eq!(
+            to_markdown(&inlines, &FormatOptions::default()),
+            "roses\\\nviolets\\\nsugar"
+        );
+
+        let options = FormatOptions {
+            hard_break: HardBreakStyle::Spaces,
+            ..Default::default()
+        };
+        assert_eq!(to_markdown(&inlines, &options), "roses  \nviolets  \nsugar");
+    }
+
+    #[test]
+    fn test_escapes_and_entities() {
+        let inlines = InlineParser::new("\\*a\\q &amp;&#x27;&nope").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Escape('*'),
+                Inline::Text("a\\q".into()),
+                Inline::Space,
+                Inline::Entity("&amp;".into()),
+                Inline::Entity("&#x27;".into()),
+                Inline::Text("&nope".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_html_verbatim() {
+        let input = "an <img width=100  src=\"a b.png\"> and <!-- a  comment -->";
+        let inlines = InlineParser::new(input).parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Text("an".into()),
+                Inline::Space,
+                Inline::Html("<img width=100  src=\"a b.png\">".into()),
+                Inline::Space,
+                Inline::Text("and".into()),
+                Inline::Space,
+                Inline::Html("<!-- a  comment -->".into()),
+            ]
+        );
+        assert_eq!(to_markdown(&inlines, &FormatOptions::default()), input);
+    }
+
+    #[test]
+    fn test_inline_math() {
+        let inlines = InlineParser::new("$x_1 * y_2$ and $`a*b`$ or $$\\sum_i$$ cost $5").parse();
+
+        assert_eq!(
+            inlines,
+            vec![
+                Inline::Math("$x_1 * y_2$".into()),
+                Inline::Space,
+                Inline::Text("and".into()),
+                Inline::Space,
+                Inline::Math("$`a*b`$".into()),
+                Inline::Space,
+                Inline::Text("or".into()),
+                Inline::Space,
+                Inline::Math("$$\\sum_i$$".into()),
+                Inline::Space,
+                Inline::Text("cost".into()),
+                Inline::Space,
+                Inline::Text("$5".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_math_not_closed() {
+        let inlines = InlineParser::new("$ 5 and $10").parse();
+
+        assert_eq!(
+            to_markdown(&inlines, &FormatOptions::default()),
+            "$ 5 and $10"
+        );
+        assert!(inlines.iter().all(|i| !matches!(i, Inline::Math(_))));
+    }
+
+    #[test]
+    fn test_wiki_links() {
+        let input = "See [[My Page|the alias]], [[Notes#Some Heading]] and ![[diagram.png]]";
+        let inlines = InlineParser::new(input).wiki_links(true).parse();
+
+        assert_eq!(
+            inlines[2],
+            Inline::WikiLink {
+                embed: false,
+                page: "My Page".into(),
+                heading: None,
+                alias: Some("the alias".into()),
+            }
+        );
+        assert_eq!(
+            inlines[9],
+            Inline::WikiLink {
+                embed: true,
+                page: "diagram.png".into(),
+                heading: None,
+                alias: None,
+            }
+        );
+        assert_eq!(to_markdown(&inlines, &FormatOptions::default()), input);
+    }
+
+    #[test]
+    fn test_wiki_links_disabled() {
+        let inlines = InlineParser::new("[[Page|alias]]").parse();
+
+        assert!(inlines
+            .iter()
+            .all(|i| !matches!(i, Inline::WikiLink { .. })));
+    }
+}
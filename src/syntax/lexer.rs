@@ -17,10 +17,15 @@ impl<'s> Iterator for Lexer<'s> {
 
     fn next(&mut self) -> Option<Self::Item> {
         self.consume_char();
-        let (_start, ch) = self.current?;
+        let (start, ch) = self.current?;
         let token = match ch {
-            '\n' => {
-                let t = self.yield_token(Newline);
+            // `\r\n` and `\r` end lines too, but read as `\n` so that the
+            // parser only deals with one line ending
+            '\r' | '\n' => {
+                if ch == '\r' {
+                    self.chars.next_if(|&(_, ch)| ch == '\n');
+                }
+                let t = Token::new(Newline, "\n".into(), (self.line, self.col), start);
                 self.col = 0;
                 self.line += 1;
                 t
@@ -29,7 +34,7 @@ impl<'s> Iterator for Lexer<'s> {
             '#' if self.is_hashtag() => self.yield_token(Hashtag),
             ch if self.is_word(ch) => {
                 let pos = (self.line, self.col);
-                Token::new(Word, self.consume_word(), pos)
+                Token::new(Word, self.consume_word(), pos, start)
             }
             _ => self.yield_token(Illegal),
         };
@@ -54,7 +59,7 @@ impl<'s> Lexer<'s> {
                 return true;
             }
         }
-        return false;
+        false
     }
 
     fn is_word(&self, ch: char) -> bool {
@@ -77,11 +82,7 @@ impl<'s> Lexer<'s> {
                 let len = next_ch.len_utf8();
                 end = next_idx + len - 1;
                 // Actually consume the character
-                let tmp_col = self.col;
-                for _ in 0..len {
-                    self.consume_char();
-                }
-                self.col = tmp_col + 1;
+                self.consume_char();
             } else {
                 break;
             }
@@ -102,6 +103,7 @@ impl<'s> Lexer<'s> {
             kind,
             self.source[start..start + ch.len_utf8()].into(),
             (self.line, self.col),
+            start,
         )
     }
 
@@ -124,8 +126,8 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Word, "hello".into(), (1, 1)),
-                Token::new(Newline, "\n".into(), (1, 6)),
+                Token::new(Word, "hello".into(), (1, 1), 0),
+                Token::new(Newline, "\n".into(), (1, 6), 5),
             ]
         );
     }
@@ -139,8 +141,8 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Word, "```rust".into(), (1, 1)),
-                Token::new(Newline, "\n".into(), (1, 8)),
+                Token::new(Word, "```rust".into(), (1, 1), 0),
+                Token::new(Newline, "\n".into(), (1, 8), 7),
             ]
         );
     }
@@ -155,11 +157,11 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Hashtag, "#".into(), (1, 1)),
-                Token::new(Whitespace, " ".into(), (1, 2)),
-                Token::new(Word, "Heading".into(), (1, 3)),
-                Token::new(Whitespace, " ".into(), (1, 10)),
-                Token::new(Word, "1".into(), (1, 11)),
+                Token::new(Hashtag, "#".into(), (1, 1), 0),
+                Token::new(Whitespace, " ".into(), (1, 2), 1),
+                Token::new(Word, "Heading".into(), (1, 3), 2),
+                Token::new(Whitespace, " ".into(), (1, 10), 9),
+                Token::new(Word, "1".into(), (1, 11), 10),
             ]
         );
     }
@@ -174,12 +176,12 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Hashtag, "#".into(), (1, 1)),
-                Token::new(Hashtag, "#".into(), (1, 2)),
-                Token::new(Whitespace, " ".into(), (1, 3)),
-                Token::new(Word, "Heading".into(), (1, 4)),
-                Token::new(Whitespace, " ".into(), (1, 11)),
-                Token::new(Word, "2".into(), (1, 12)),
+                Token::new(Hashtag, "#".into(), (1, 1), 0),
+                Token::new(Hashtag, "#".into(), (1, 2), 1),
+                Token::new(Whitespace, " ".into(), (1, 3), 2),
+                Token::new(Word, "Heading".into(), (1, 4), 3),
+                Token::new(Whitespace, " ".into(), (1, 11), 10),
+                Token::new(Word, "2".into(), (1, 12), 11),
             ]
         );
     }
@@ -194,12 +196,12 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Whitespace, " ".into(), (1, 1)),
-                Token::new(Whitespace, " ".into(), (1, 2)),
-                Token::new(Hashtag, "#".into(), (1, 3)),
-                Token::new(Hashtag, "#".into(), (1, 4)),
-                Token::new(Whitespace, " ".into(), (1, 5)),
-                Token::new(Word, "Heading".into(), (1, 6)),
+                Token::new(Whitespace, " ".into(), (1, 1), 0),
+                Token::new(Whitespace, " ".into(), (1, 2), 1),
+                Token::new(Hashtag, "#".into(), (1, 3), 2),
+                Token::new(Hashtag, "#".into(), (1, 4), 3),
+                Token::new(Whitespace, " ".into(), (1, 5), 4),
+                Token::new(Word, "Heading".into(), (1, 6), 5),
             ]
         );
     }
@@ -214,11 +216,11 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Newline, "\n".into(), (1, 1)),
-                Token::new(Hashtag, "#".into(), (2, 1)),
-                Token::new(Hashtag, "#".into(), (2, 2)),
-                Token::new(Whitespace, " ".into(), (2, 3)),
-                Token::new(Word, "Heading".into(), (2, 4)),
+                Token::new(Newline, "\n".into(), (1, 1), 0),
+                Token::new(Hashtag, "#".into(), (2, 1), 1),
+                Token::new(Hashtag, "#".into(), (2, 2), 2),
+                Token::new(Whitespace, " ".into(), (2, 3), 3),
+                Token::new(Word, "Heading".into(), (2, 4), 4),
             ]
         );
     }
@@ -229,7 +231,10 @@ mod tests {
 
         let tokens: Vec<Token> = lexer.collect();
 
-        assert_eq!(tokens, vec![Token::new(Word, "#Heading".into(), (1, 1)),]);
+        assert_eq!(
+            tokens,
+            vec![Token::new(Word, "#Heading".into(), (1, 1), 0),]
+        );
     }
 
     #[test]
@@ -242,15 +247,15 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Hashtag, "#".into(), (1, 1)),
-                Token::new(Hashtag, "#".into(), (1, 2)),
-                Token::new(Hashtag, "#".into(), (1, 3)),
-                Token::new(Hashtag, "#".into(), (1, 4)),
-                Token::new(Hashtag, "#".into(), (1, 5)),
-                Token::new(Hashtag, "#".into(), (1, 6)),
-                Token::new(Hashtag, "#".into(), (1, 7)),
-                Token::new(Whitespace, " ".into(), (1, 8)),
-                Token::new(Word, "Heading".into(), (1, 9))
+                Token::new(Hashtag, "#".into(), (1, 1), 0),
+                Token::new(Hashtag, "#".into(), (1, 2), 1),
+                Token::new(Hashtag, "#".into(), (1, 3), 2),
+                Token::new(Hashtag, "#".into(), (1, 4), 3),
+                Token::new(Hashtag, "#".into(), (1, 5), 4),
+                Token::new(Hashtag, "#".into(), (1, 6), 5),
+                Token::new(Hashtag, "#".into(), (1, 7), 6),
+                Token::new(Whitespace, " ".into(), (1, 8), 7),
+                Token::new(Word, "Heading".into(), (1, 9), 8)
             ]
         );
     }
@@ -265,13 +270,42 @@ mod tests {
         assert_eq!(
             tokens,
             vec![
-                Token::new(Whitespace, " ".into(), (1, 1)),
-                Token::new(Whitespace, " ".into(), (1, 2)),
-                Token::new(Whitespace, " ".into(), (1, 3)),
-                Token::new(Hashtag, "#".into(), (1, 4)),
-                Token::new(Hashtag, "#".into(), (1, 5)),
-                Token::new(Whitespace, " ".into(), (1, 6)),
-                Token::new(Word, "Heading".into(), (1, 7))
+                Token::new(Whitespace, " ".into(), (1, 1), 0),
+                Token::new(Whitespace, " ".into(), (1, 2), 1),
+                Token::new(Whitespace, " ".into(), (1, 3), 2),
+                Token::new(Hashtag, "#".into(), (1, 4), 3),
+                Token::new(Hashtag, "#".into(), (1, 5), 4),
+                Token::new(Whitespace, " ".into(), (1, 6), 5),
+                Token::new(Word, "Heading".into(), (1, 7), 6)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let tokens: Vec<Token> = Lexer::new("a\r\nb\rc").collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(Word, "a".into(), (1, 1), 0),
+                Token::new(Newline, "\n".into(), (1, 2), 1),
+                Token::new(Word, "b".into(), (2, 1), 3),
+                Token::new(Newline, "\n".into(), (2, 2), 4),
+                Token::new(Word, "c".into(), (3, 1), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multibyte_words() {
+        let tokens: Vec<Token> = Lexer::new("名前 😀é").collect();
+        assert_eq!(
+            tokens,
+            vec![
+                Token::new(Word, "名前".into(), (1, 1), 0),
+                Token::new(Whitespace, " ".into(), (1, 3), 6),
+                Token::new(Illegal, "😀".into(), (1, 4), 7),
+                Token::new(Word, "é".into(), (1, 5), 11),
             ]
         );
     }
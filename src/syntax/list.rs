@@ -0,0 +1,309 @@
+use super::footnote;
+use super::stmt::{self, Stmt};
+use crate::options::FormatOptions;
+
+/// The marker a list item starts with
+#[derive(Clone, Debug, PartialEq)]
+pub enum Marker {
+    /// `-`, `+` or `*`
+    Bullet(char),
+    /// A number followed by `.` or `)`
+    Ordered { number: usize, delimiter: char },
+}
+
+impl Marker {
+    pub fn to_markdown(&self) -> String {
+        match self {
+            Marker::Bullet(bullet) => bullet.to_string(),
+            Marker::Ordered { number, delimiter } => format!("{}{}", number, delimiter),
+        }
+    }
+
+    /// Checks whether an item with `other` continues a list with this marker
+    pub fn continues(&self, other: &Marker) -> bool {
+        match (self, other) {
+            (Marker::Bullet(a), Marker::Bullet(b)) => a == b,
+            (Marker::Ordered { delimiter: a, .. }, Marker::Ordered { delimiter: b, .. }) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListItem {
+    pub marker: Marker,
+    pub content: Vec<Stmt>,
+}
+
+/// The start of a list item line
+#[derive(Clone, Debug, PartialEq)]
+pub struct ItemStart {
+    pub marker: Marker,
+    /// Column the item content starts at, which continuation lines are
+    /// indented to
+    pub indent: usize,
+    /// Text after the marker
+    pub rest: String,
+}
+
+/// Recognises a line starting a list item
+pub fn parse_item_start(line: &str) -> Option<ItemStart> {
+    if is_thematic_break(line) {
+        return None;
+    }
+    let text = line.trim_start_matches(' ');
+    let leading = line.len() - text.len();
+    if leading > 3 {
+        return None;
+    }
+
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    let (marker, after) = match text.chars().next()? {
+        bullet @ ('-' | '+' | '*') => (Marker::Bullet(bullet), &text[1..]),
+        _ if (1..=9).contains(&digits) => {
+            let delimiter = text[digits..]
+                .chars()
+                .next()
+                .filter(|c| *c == '.' || *c == ')')?;
+            let number = text[..digits].parse().ok()?;
+            (Marker::Ordered { number, delimiter }, &text[digits + 1..])
+        }
+        _ => return None,
+    };
+
+    let rest = after.trim_start_matches([' ', '\t']);
+    if rest.len() == after.len() && !after.is_empty() {
+        return None;
+    }
+    let spaces = footnote::indentation(after);
+    // Content indented by five or more columns is an indented code block
+    // after a single space
+    let spaces = if rest.is_empty() || spaces > 4 {
+        1
+    } else {
+        spaces
+    };
+    Some(ItemStart {
+        indent: line.len() - after.len() + spaces,
+        rest: if spaces == 1 && !rest.is_empty() {
+            after[1..].into()
+        } else {
+            rest.into()
+        },
+        marker,
+    })
+}
+
+/// Checks for a line of three or more `-`, `*` or `_` and nothing else but
+/// spaces and tabs
+pub fn is_thematic_break(line: &str) -> bool {
+    let text = line.trim_start_matches(' ');
+    if line.len() - text.len() > 3 {
+        return false;
+    }
+    let mut chars = text.chars().filter(|c| *c != ' ' && *c != '\t');
+    let Some(first @ ('-' | '*' | '_')) = chars.next() else {
+        return false;
+    };
+    let mut count = 1;
+    for ch in chars {
+        if ch != first {
+            return false;
+        }
+        count += 1;
+    }
+    count >= 3
+}
+
+/// Checks whether a blank line in the lines of an item body separates two of
+/// its blocks, which makes the list loose. Blank lines inside fenced code and
+/// between the items of a nested list do not.
+pub fn separates_blocks(body: &[String]) -> bool {
+    let nested = |line: &str| footnote::indentation(line) > 0 || parse_item_start(line).is_some();
+    let mut previous: Option<&str> = None;
+    let mut fenced = false;
+    let mut blank = false;
+    for line in body {
+        if line.trim().is_empty() {
+            blank = previous.is_some() && !fenced;
+            continue;
+        }
+        if blank && !previous.is_some_and(|previous| nested(previous) && nested(line)) {
+            return true;
+        }
+        blank = false;
+        if line.trim_start().starts_with("```") {
+            fenced = !fenced;
+        }
+        previous = Some(line);
+    }
+    false
+}
+
+/// Writes every bullet list with `bullet`, nested ones included. A list
+/// right after another bullet list gets a different bullet, so that the two
+/// stay apart.
+pub fn set_bullets(stmts: &mut [Stmt], bullet: char) {
+    let alternate = if bullet == '-' { '*' } else { '-' };
+    let mut previous = None;
+    for stmt in stmts.iter_mut() {
+        let mut current = None;
+        match stmt {
+            Stmt::List { items, .. } => {
+                if let Some(Marker::Bullet(_)) = items.first().map(|item| &item.marker) {
+                    let chosen = if previous == Some(bullet) {
+                        alternate
+                    } else {
+                        bullet
+                    };
+                    for item in items.iter_mut() {
+                        item.marker = Marker::Bullet(chosen);
+                    }
+                    current = Some(chosen);
+                }
+                for item in items.iter_mut() {
+                    set_bullets(&mut item.content, bullet);
+                }
+            }
+            Stmt::BlockQuote { content } | Stmt::FootnoteDefinition { content, .. } => {
+                set_bullets(content, bullet)
+            }
+            Stmt::DefinitionList { items } => items
+                .iter_mut()
+                .flat_map(|item| item.definitions.iter_mut())
+                .for_each(|content| set_bullets(content, bullet)),
+            _ => {}
+        }
+        previous = current;
+    }
+}
+
+/// Converts a list into Markdown, indenting the blocks of every item to line
+/// up with the text after its marker. That text starts at the indent width
+/// when the marker is shorter, unless the padding would read as code.
+pub fn to_markdown(items: &[ListItem], loose: bool, options: &FormatOptions) -> String {
+    let separator = if loose { "\n\n" } else { "\n" };
+    items
+        .iter()
+        .map(|item| {
+            let marker = item.marker.to_markdown();
+            let padding = match item.content.first() {
+                Some(Stmt::IndentedCodeBlock { .. }) => 1,
+                _ => options
+                    .indent_width
+                    .saturating_sub(marker.len())
+                    .clamp(1, 4),
+            };
+            let indent = " ".repeat(marker.len() + padding);
+            let body = stmt::indented(&item.content, &indent, separator, options);
+            if body.is_empty() {
+                marker
+            } else {
+                format!("{}{}", marker, &body[marker.len()..])
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::{ListMarker, ProseWrap};
+    use crate::syntax::format;
+
+    #[test]
+    fn test_parse_item_start() {
+        assert_eq!(
+            parse_item_start("- item"),
+            Some(ItemStart {
+                marker: Marker::Bullet('-'),
+                indent: 2,
+                rest: "item".into(),
+            })
+        );
+        assert_eq!(
+            parse_item_start(" 10)   item"),
+            Some(ItemStart {
+                marker: Marker::Ordered {
+                    number: 10,
+                    delimiter: ')'
+                },
+                indent: 7,
+                rest: "item".into(),
+            })
+        );
+        assert_eq!(parse_item_start("*").map(|start| start.indent), Some(2));
+        assert_eq!(
+            parse_item_start("-      code").map(|start| start.rest),
+            Some("     code".into())
+        );
+        assert_eq!(parse_item_start("-item"), None);
+        assert_eq!(parse_item_start("1.5 million"), None);
+        assert_eq!(parse_item_start("* * *"), None);
+        assert_eq!(parse_item_start("    - code"), None);
+    }
+
+    #[test]
+    fn test_is_thematic_break() {
+        assert!(is_thematic_break("***"));
+        assert!(is_thematic_break(" - - -  "));
+        assert!(is_thematic_break("_____"));
+        assert!(!is_thematic_break("--"));
+        assert!(!is_thematic_break("-*-"));
+        assert!(!is_thematic_break("    ---"));
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let input = "- one\n- two\n  - nested\n    more\n* other\n\n1. a\n\n   b\n2) c";
+
+        assert_eq!(
+            format(input, &FormatOptions::default()),
+            "- one\n- two\n  - nested\n    more\n\n* other\n\n1. a\n\n   b\n\n2) c"
+        );
+    }
+
+    #[test]
+    fn test_set_bullets() {
+        let options = FormatOptions {
+            list_marker: ListMarker::Plus,
+            ..Default::default()
+        };
+        let input = "- a\n  * b\n\n* c\n\n1. d\n\n> - e";
+
+        assert_eq!(
+            format(input, &options),
+            "+ a\n  + b\n\n- c\n\n1. d\n\n> + e"
+        );
+    }
+
+    #[test]
+    fn test_indent_width() {
+        let options = FormatOptions {
+            indent_width: 4,
+            ..Default::default()
+        };
+        let input = "- a\n  - b\n\n    c\n1. d\n10. e\n100. f\n-      code";
+
+        assert_eq!(
+            format(input, &options),
+            "-   a\n    -   b\n\n        c\n\n1.  d\n10. e\n100. f\n\n-      code"
+        );
+    }
+
+    #[test]
+    fn test_reflow_items() {
+        let options = FormatOptions {
+            prose_wrap: ProseWrap::Always,
+            line_width: 14,
+            ..Default::default()
+        };
+        let input = "- list item one\n- list item two and more\n\n10. ordered item text";
+
+        assert_eq!(
+            format(input, &options),
+            "- list item\n  one\n- list item\n  two and more\n\n10. ordered\n    item text"
+        );
+    }
+}
@@ -1,7 +1,18 @@
+use super::attributes::{self, Attributes};
+use super::definition::{self, DefinitionItem};
+use super::footnote;
+use super::front_matter::FrontMatterFormat;
+use super::html::{self, BlockKind};
+use super::ignore::{self, Directive};
+use super::inline::{Inline, InlineParser};
+use super::lexer::Lexer;
+use super::list::{self, ItemStart, ListItem, Marker};
+use super::quote;
 use super::stmt::Stmt;
+use super::table::{self, Alignment};
 use super::tokens::Token;
 use super::tokens::TokenKind::*;
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::iter::Peekable;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -16,60 +27,44 @@ impl SyntaxError {
     }
 }
 
+impl fmt::Display for SyntaxError {
+    /// Writes the error as `line:col: msg`
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.pos.0, self.pos.1, self.msg)
+    }
+}
+
 #[derive(Clone, Debug)]
-pub struct Parser<I: Iterator<Item = Token> + Clone + Debug> {
+pub struct Parser<'s, I: Iterator<Item = Token> + Clone + Debug> {
     tokens: Peekable<I>,
+    /// Source the tokens were read from, which ignored regions are copied
+    /// from byte for byte
+    source: &'s str,
+    /// Parse `[[wiki links]]` and `![[embeds]]` in inline content
+    wiki_links: bool,
 }
 
-impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
-    pub fn new(tokens: I) -> Self {
+impl<'s, I: Iterator<Item = Token> + Clone + Debug> Parser<'s, I> {
+    /// Creates a parser for the `tokens` read from `source`
+    pub fn new(source: &'s str, tokens: I) -> Self {
         Parser {
             tokens: tokens.peekable(),
+            source,
+            wiki_links: false,
         }
     }
 
+    pub fn wiki_links(mut self, enabled: bool) -> Self {
+        self.wiki_links = enabled;
+        self
+    }
+
     pub fn parse(&mut self) -> Result<Vec<Stmt>, Vec<SyntaxError>> {
         let mut statements = vec![];
         let mut errors = vec![];
 
         while let Some(token) = self.tokens.next() {
-            match token.kind {
-                Hashtag => {
-                    // Try to parse a heading
-                    match self.parse_heading(&token) {
-                        Ok(heading) => statements.push(heading),
-                        Err(error) => errors.push(error),
-                    }
-                }
-                Word | Whitespace => {
-                    if self.is_front_matter(&token) {
-                        match self.parse_front_matter() {
-                            Ok(font_matter) => statements.push(font_matter),
-                            Err(error) => errors.push(error),
-                        }
-                    } else if self.is_code_block_start(&token) {
-                        match self.parse_code_block(&token) {
-                            Ok(code_block) => statements.push(code_block),
-                            Err(error) => errors.push(error),
-                        }
-                    } else {
-                        // Parse a paragraph
-                        let paragraph = self.parse_paragraph(token);
-                        statements.push(paragraph);
-                    }
-                }
-                Newline => {
-                    // Skip single newlines but detect blank lines
-                    self.skip_blank_lines();
-                }
-                _ => {
-                    // Handle unexpected tokens
-                    errors.push(SyntaxError::new(
-                        format!("Unexpected token: {:?}", token.kind),
-                        token.pos,
-                    ));
-                }
-            }
+            self.parse_block(token, &mut statements, &mut errors);
         }
 
         if errors.is_empty() {
@@ -79,13 +74,101 @@ impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
         }
     }
 
-    fn is_front_matter(&self, first_token: &Token) -> bool {
-        // TODO: Check if prev token is newline
-        if first_token.text != "---" {
-            return false;
+    /// Parses the block starting with `token`, if any
+    fn parse_block(
+        &mut self,
+        token: Token,
+        statements: &mut Vec<Stmt>,
+        errors: &mut Vec<SyntaxError>,
+    ) {
+        match token.kind {
+            Hashtag => {
+                // Try to parse a heading
+                match self.parse_heading(&token) {
+                    Ok(heading) => statements.push(heading),
+                    Err(error) => errors.push(error),
+                }
+            }
+            Whitespace if self.is_blank_line() => {
+                // Skip whitespace-only lines
+                self.consume_while(|t| t.kind == Whitespace);
+            }
+            // Other characters, like emoji, start paragraphs as well
+            Word | Whitespace | Illegal => {
+                if footnote::indentation(&self.peek_line(&token)) >= 4 {
+                    let code_block = self.parse_indented_code_block(&token);
+                    statements.push(code_block);
+                } else if let Some(format) = self.front_matter_format(&token) {
+                    let front_matter = self.parse_front_matter(&token, format);
+                    statements.push(front_matter);
+                } else if let Some((label, rest)) =
+                    footnote::parse_definition_start(&self.peek_line(&token))
+                {
+                    match self.parse_footnote_definition(&token, label, rest) {
+                        Ok(definition) => statements.push(definition),
+                        Err(mut nested) => errors.append(&mut nested),
+                    }
+                } else if let Some(directive @ (Directive::Next | Directive::Start)) =
+                    ignore::directive(&self.peek_line(&token))
+                {
+                    let verbatim = self.parse_ignored(&token, directive);
+                    statements.push(verbatim);
+                } else if self.is_math_block_start(&token) {
+                    let math_block = self.parse_math_block(&token);
+                    statements.push(math_block);
+                } else if let Some(kind) = html::block_start(&self.peek_line(&token)) {
+                    let html_block = self.parse_html_block(&token, kind);
+                    statements.push(html_block);
+                } else if let Some(alignments) = self.is_table_start(&token) {
+                    let table = self.parse_table(&token, alignments);
+                    statements.push(table);
+                } else if self.is_code_block_start(&token) {
+                    match self.parse_code_block(&token) {
+                        Ok(code_block) => statements.push(code_block),
+                        Err(error) => errors.push(error),
+                    }
+                } else if list::is_thematic_break(&self.peek_line(&token)) {
+                    let content = self.peek_line(&token).trim().into();
+                    self.consume_line();
+                    statements.push(Stmt::ThematicBreak { content });
+                } else if quote::parse_quote_line(&self.peek_line(&token)).is_some() {
+                    match self.parse_block_quote(&token) {
+                        Ok(quote) => statements.push(quote),
+                        Err(mut nested) => errors.append(&mut nested),
+                    }
+                } else if let Some(start) = list::parse_item_start(&self.peek_line(&token)) {
+                    match self.parse_list(&token, start) {
+                        Ok(list) => statements.push(list),
+                        Err(mut nested) => errors.append(&mut nested),
+                    }
+                } else if definition::follows_term(self.upcoming_lines().skip(1)) {
+                    match self.parse_definition_list(&token) {
+                        Ok(list) => statements.push(list),
+                        Err(mut nested) => errors.append(&mut nested),
+                    }
+                } else {
+                    // Parse a paragraph
+                    let paragraph = self.parse_paragraph(token);
+                    statements.push(paragraph);
+                }
+            }
+            Newline => {
+                // Skip single newlines but detect blank lines
+                self.skip_blank_lines();
+            }
         }
-        // TODO: Check if line contains only spaces and ends with newline
-        true
+    }
+
+    /// Detects front matter, which must open the document and be closed
+    fn front_matter_format(&self, first_token: &Token) -> Option<FrontMatterFormat> {
+        if first_token.pos != (1, 1) {
+            return None;
+        }
+        let format = FrontMatterFormat::from_opening(&self.peek_line(first_token))?;
+        self.upcoming_lines()
+            .skip(1)
+            .any(|line| line.trim_end() == format.closing())
+            .then_some(format)
     }
 
     fn is_code_block_start(&self, first_token: &Token) -> bool {
@@ -97,22 +180,111 @@ impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
         true
     }
 
-    fn parse_front_matter(&mut self) -> Result<Stmt, SyntaxError> {
-        self.consume_while(|t| t.kind == Whitespace);
-        if let Some(token) = self.tokens.next() {
-            assert_eq!(token.kind, Newline);
+    /// Checks for a line holding only `$$`, followed by a line ending with the
+    /// closing `$$`, or for a whole line of `$$ ... $$`
+    fn is_math_block_start(&self, first_token: &Token) -> bool {
+        let line = self.peek_line(first_token);
+        let text = line.trim_start_matches(' ');
+        if line.len() - text.len() > 3 {
+            return false;
+        }
+        match text.trim_end() {
+            "$$" => self
+                .upcoming_lines()
+                .skip(1)
+                .any(|line| line.trim_end().ends_with("$$")),
+            text => text.len() >= 4 && text.starts_with("$$") && text.ends_with("$$"),
         }
+    }
+
+    /// Checks for a header row followed by a delimiter row with as many cells
+    fn is_table_start(&self, first_token: &Token) -> Option<Vec<Alignment>> {
+        let delimiter_row = self.upcoming_lines().nth(1)?;
+        Self::table_alignments(&self.peek_line(first_token), &delimiter_row)
+    }
+
+    fn table_alignments(header: &str, delimiter_row: &str) -> Option<Vec<Alignment>> {
+        if !header.contains('|') {
+            return None;
+        }
+        let alignments = table::parse_delimiter_row(delimiter_row)?;
+
+        (alignments.len() == table::split_cells(header).len()).then_some(alignments)
+    }
+
+    /// Checks whether the upcoming line starts a block that ends a paragraph
+    /// without a blank line in between
+    fn interrupts_paragraph(&self) -> bool {
+        let mut lines = self.upcoming_lines();
+        let Some(line) = lines.next() else {
+            return false;
+        };
+        let text = line.trim_start_matches(' ');
+        if line.len() - text.len() > 3 {
+            return false;
+        }
+
+        let hashes = text.chars().take_while(|c| *c == '#').count();
+        let is_heading = (1..=6).contains(&hashes) && text[hashes..].starts_with([' ', '\t']);
+        // Lists interrupting a paragraph must start with content, and ordered
+        // ones at 1, so that wrapped numbers keep reading as text
+        let is_list = list::parse_item_start(&line).is_some_and(|start| {
+            !start.rest.trim().is_empty()
+                && matches!(
+                    start.marker,
+                    Marker::Bullet(_) | Marker::Ordered { number: 1, .. }
+                )
+        });
+        is_heading
+            || is_list
+            || line.starts_with("```")
+            || list::is_thematic_break(&line)
+            || quote::parse_quote_line(&line).is_some()
+            || html::block_start(&line).is_some_and(|kind| kind != BlockKind::Tag)
+            || lines
+                .next()
+                .is_some_and(|next| Self::table_alignments(&line, &next).is_some())
+    }
+
+    /// Returns the heading level a setext underline of `=` or `-` gives the
+    /// paragraph above it
+    fn setext_level(line: &str) -> Option<usize> {
+        let text = line.trim_start_matches(' ');
+        if line.len() - text.len() > 3 {
+            return None;
+        }
+        let text = text.trim_end();
+        let underline = text.chars().next()?;
+        match underline {
+            '=' if text.chars().all(|c| c == '=') => Some(1),
+            '-' if text.chars().all(|c| c == '-') => Some(2),
+            _ => None,
+        }
+    }
+
+    fn parse_front_matter(&mut self, first_token: &Token, format: FrontMatterFormat) -> Stmt {
+        self.consume_line(); // Opening delimiter
 
-        // Collect code block content
         let mut content = String::new();
-        while let Some(token) = self.tokens.next() {
-            if token.text == "---" {
+        while let Some(line) = self.upcoming_lines().next() {
+            self.consume_line();
+            if line.trim_end() == format.closing() {
                 break;
             }
-            content.push_str(&token.text);
+            content.push_str(&line);
+            content.push('\n');
+        }
+
+        // Bare JSON front matter keeps its braces
+        if format == (FrontMatterFormat::Json { fenced: false }) {
+            content = format!("{{\n{}}}", content);
         }
 
-        Ok(Stmt::FrontMatter { content })
+        Stmt::FrontMatter {
+            format,
+            content,
+            pos: first_token.pos,
+        }
     }
 
     fn parse_code_block(&mut self, first_token: &Token) -> Result<Stmt, SyntaxError> {
@@ -139,7 +311,7 @@ impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
 
         // Collect code block content
         let mut content = String::new();
-        while let Some(token) = self.tokens.next() {
+        for token in self.tokens.by_ref() {
             if token.text == "```" {
                 break;
             }
@@ -149,6 +321,276 @@ impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
         Ok(Stmt::CodeBlock { language, content })
     }
 
+    /// Parses a code block indented by four columns, which blank lines only
+    /// end when no indented line follows
+    fn parse_indented_code_block(&mut self, first_token: &Token) -> Stmt {
+        let mut lines = vec![footnote::dedent(&self.peek_line(first_token), 4)];
+        self.consume_line();
+        while let Some(line) = self.upcoming_lines().next() {
+            if line.trim().is_empty() {
+                let next = self.upcoming_lines().find(|l| !l.trim().is_empty());
+                if next.is_none_or(|l| footnote::indentation(&l) < 4) {
+                    break;
+                }
+            } else if footnote::indentation(&line) < 4 {
+                break;
+            }
+            lines.push(footnote::dedent(&line, 4));
+            self.consume_line();
+        }
+
+        Stmt::IndentedCodeBlock {
+            content: lines.join("\n"),
+        }
+    }
+
+    /// Parses a `$$` display math block, keeping everything between the
+    /// delimiters verbatim
+    fn parse_math_block(&mut self, first_token: &Token) -> Stmt {
+        let line = self.peek_line(first_token);
+        self.consume_line();
+        let after = &line.trim_start()[2..];
+
+        // One-line `$$ ... $$` blocks
+        if let Some(inner) = after.trim_end().strip_suffix("$$") {
+            return Stmt::MathBlock {
+                content: inner.into(),
+            };
+        }
+
+        let mut content = after.trim_end().to_string();
+        while let Some(line) = self.upcoming_lines().next() {
+            self.consume_line();
+            content.push('\n');
+            if let Some(inner) = line.trim_end().strip_suffix("$$") {
+                content.push_str(inner);
+                break;
+            }
+            content.push_str(&line);
+        }
+
+        Stmt::MathBlock { content }
+    }
+
+    /// Captures an ignored region exactly as written: the directive and the
+    /// next block, or everything up to the end directive
+    fn parse_ignored(&mut self, first_token: &Token, directive: Directive) -> Stmt {
+        let start = first_token.offset;
+        self.consume_line();
+        if directive == Directive::Start {
+            while self.tokens.peek().is_some() {
+                let line = self.consume_line();
+                if ignore::directive(&line) == Some(Directive::End) {
+                    break;
+                }
+            }
+        } else {
+            self.consume_blank_lines();
+            // The block is parsed as usual, only to find where it ends
+            if let Some(token) = self.tokens.next() {
+                self.parse_block(token, &mut vec![], &mut vec![]);
+            }
+        }
+
+        // The region ends before the line break that ended its last line
+        let end = self
+            .tokens
+            .peek()
+            .map_or(self.source.len(), |token| token.offset);
+        Stmt::Verbatim {
+            content: self.source[start..end]
+                .trim_end_matches(['\r', '\n'])
+                .into(),
+        }
+    }
+
+    fn parse_html_block(&mut self, first_token: &Token, kind: BlockKind) -> Stmt {
+        let mut content = first_token.text.clone();
+        content.push_str(&self.consume_line());
+
+        let mut line = content.clone();
+        while !kind.ends(&line) && self.tokens.peek().is_some() {
+            // Blocks without an end marker stop at a blank line
+            if matches!(kind, BlockKind::Block | BlockKind::Tag) && self.is_blank_line() {
+                break;
+            }
+            line = self.consume_line();
+            content.push('\n');
+            content.push_str(&line);
+        }
+
+        Stmt::HtmlBlock { content }
+    }
+
+    fn parse_table(&mut self, first_token: &Token, alignments: Vec<Alignment>) -> Stmt {
+        let wiki_links = self.wiki_links;
+        let parse_row = |line: &str| {
+            table::split_cells(line)
+                .iter()
+                .map(|cell| InlineParser::new(cell).wiki_links(wiki_links).parse())
+                .collect::<Vec<_>>()
+        };
+
+        let header = parse_row(&format!("{}{}", first_token.text, self.consume_line()));
+        self.consume_line(); // Delimiter row
+
+        // Body rows have as many cells as the header: extra cells are
+        // dropped and missing ones left empty
+        let mut rows = vec![];
+        while self.tokens.peek().is_some() && !self.is_blank_line() {
+            let mut row = parse_row(&self.consume_line());
+            row.resize(header.len(), vec![]);
+            rows.push(row);
+        }
+
+        Stmt::Table {
+            alignments,
+            header,
+            rows,
+        }
+    }
+
+    fn parse_footnote_definition(
+        &mut self,
+        first_token: &Token,
+        label: String,
+        rest: String,
+    ) -> Result<Stmt, Vec<SyntaxError>> {
+        self.consume_line(); // Marker line
+        let content = self.parse_container_body(rest, first_token.pos.0, 4, |line| {
+            footnote::parse_definition_start(line).is_some()
+        })?;
+
+        Ok(Stmt::FootnoteDefinition { label, content })
+    }
+
+    fn parse_definition_list(&mut self, first_token: &Token) -> Result<Stmt, Vec<SyntaxError>> {
+        let mut items = vec![];
+        let mut term = format!("{}{}", first_token.text, self.consume_line());
+        loop {
+            let mut loose = self.consume_blank_lines() > 0;
+            let mut definitions = vec![];
+            while let Some(rest) = self
+                .upcoming_lines()
+                .next()
+                .and_then(|line| definition::parse_definition_start(&line))
+            {
+                let line = self.tokens.peek().map_or(0, |t| t.pos.0);
+                self.consume_line();
+                definitions.push(self.parse_container_body(rest, line, 4, |line| {
+                    definition::parse_definition_start(line).is_some()
+                })?);
+
+                // Blank lines between definitions make the item loose
+                let blanks = self
+                    .upcoming_lines()
+                    .take_while(|l| l.trim().is_empty())
+                    .count();
+                let next = self.upcoming_lines().nth(blanks).unwrap_or_default();
+                if blanks > 0 && definition::parse_definition_start(&next).is_some() {
+                    self.consume_blank_lines();
+                    loose = true;
+                }
+            }
+            items.push(DefinitionItem {
+                term: self.parse_inlines(term.trim()),
+                definitions,
+                loose,
+            });
+
+            // Another term must follow after blank lines
+            let blanks = self
+                .upcoming_lines()
+                .take_while(|l| l.trim().is_empty())
+                .count();
+            let next = self.upcoming_lines().nth(blanks).unwrap_or_default();
+            if blanks == 0
+                || next.trim_start().starts_with('#')
+                || !definition::follows_term(self.upcoming_lines().skip(blanks + 1))
+            {
+                break;
+            }
+            self.consume_blank_lines();
+            term = self.consume_line();
+        }
+
+        Ok(Stmt::DefinitionList { items })
+    }
+
+    /// Parses a block quote, whose lines start with `>` unless they continue
+    /// a paragraph lazily
+    fn parse_block_quote(&mut self, first_token: &Token) -> Result<Stmt, Vec<SyntaxError>> {
+        let mut body = vec![];
+        let mut lazy = false;
+        let mut line = self.peek_line(first_token);
+        loop {
+            if let Some(rest) = quote::parse_quote_line(&line) {
+                lazy = !rest.trim().is_empty();
+                body.push(rest);
+            } else if lazy && !line.trim().is_empty() && !self.interrupts_paragraph() {
+                body.push(line.trim_start().into());
+            } else {
+                break;
+            }
+            self.consume_line();
+            match self.upcoming_lines().next() {
+                Some(next) => line = next,
+                None => break,
+            }
+        }
+
+        let content = self.parse_nested(&body, first_token.pos.0)?;
+        Ok(Stmt::BlockQuote { content })
+    }
+
+    /// Parses a list of items with the same kind of marker. Blank lines
+    /// between the items or the blocks inside them make it loose.
+    fn parse_list(
+        &mut self,
+        first_token: &Token,
+        start: ItemStart,
+    ) -> Result<Stmt, Vec<SyntaxError>> {
+        let kind = start.marker.clone();
+        let mut items = vec![];
+        let mut loose = false;
+        let mut line = first_token.pos.0;
+        let mut next = Some(start);
+
+        while let Some(ItemStart {
+            marker,
+            indent,
+            rest,
+        }) = next.take()
+        {
+            self.consume_line(); // Marker line
+            let body = self.collect_container_body(rest, indent, |line| {
+                list::parse_item_start(line).is_some()
+            });
+            loose |= list::separates_blocks(&body);
+            items.push(ListItem {
+                marker,
+                content: self.parse_nested(&body, line)?,
+            });
+
+            let blanks = self
+                .upcoming_lines()
+                .take_while(|l| l.trim().is_empty())
+                .count();
+            next = self
+                .upcoming_lines()
+                .nth(blanks)
+                .and_then(|l| list::parse_item_start(&l))
+                .filter(|start| kind.continues(&start.marker));
+            if next.is_some() {
+                loose |= blanks > 0;
+                self.consume_blank_lines();
+                line = self.tokens.peek().map_or(line, |t| t.pos.0);
+            }
+        }
+
+        Ok(Stmt::List { items, loose })
+    }
+
     fn parse_heading(&mut self, first_token: &Token) -> Result<Stmt, SyntaxError> {
         let mut level = 1; // First `Hashtag` already counted
         while let Some(Hashtag) = self.tokens.peek().map(|t| &t.kind) {
@@ -175,28 +617,167 @@ impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
             }
         }
 
-        Ok(Stmt::Heading { level, content })
+        // An optional closing run of `#` is not part of the content
+        let mut content = content.trim();
+        let closing = content.trim_end_matches('#');
+        if closing.is_empty() || closing.ends_with([' ', '\t']) {
+            content = closing.trim_end();
+        }
+        Ok(self.heading(level, content))
+    }
+
+    /// Builds a heading from its text, splitting off trailing attributes
+    fn heading(&self, level: usize, content: &str) -> Stmt {
+        let (content, attributes) = match attributes::split_trailing(content) {
+            Some((text, attributes)) => (text, attributes),
+            None => (content, Attributes::default()),
+        };
+        Stmt::Heading {
+            level,
+            content: self.parse_inlines(content),
+            attributes,
+        }
     }
 
+    /// Parses a paragraph, which ends at a blank line or a line starting
+    /// another block, or becomes a heading when a setext underline follows
     fn parse_paragraph(&mut self, first_token: Token) -> Stmt {
-        let mut words = vec![first_token];
-        while let Some(token) = self.tokens.peek() {
-            match token.kind {
-                Newline => {
-                    // Check for blank lines to end the paragraph
-                    self.tokens.next(); // Consume the newline
-                    if let Some(Newline) = self.tokens.peek().map(|t| &t.kind) {
-                        break; // End of paragraph
-                    }
+        // Continuation lines lose their indentation
+        let mut text = self.peek_line(&first_token).trim_start().to_string();
+        self.consume_line();
+        while let Some(line) = self.upcoming_lines().next() {
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some(level) = Self::setext_level(&line) {
+                self.consume_line();
+                // ATX headings, which it is written as, take a single line
+                let text = text.split('\n').map(str::trim).collect::<Vec<_>>();
+                return self.heading(level, &text.join(" "));
+            }
+            if self.interrupts_paragraph() {
+                break;
+            }
+            text.push('\n');
+            text.push_str(line.trim_start());
+            self.consume_line();
+        }
+
+        let inlines = self.parse_inlines(&text);
+        Stmt::Paragraph { inlines }
+    }
+
+    /// Collects the body of a container block whose first line has been
+    /// consumed, leaving `first` as the text after its marker, and parses it as
+    /// blocks starting on `line`.
+    fn parse_container_body(
+        &mut self,
+        first: String,
+        line: usize,
+        indent: usize,
+        starts_sibling: fn(&str) -> bool,
+    ) -> Result<Vec<Stmt>, Vec<SyntaxError>> {
+        let body = self.collect_container_body(first, indent, starts_sibling);
+        self.parse_nested(&body, line)
+    }
+
+    /// Collects the lines of a container body. Following lines belong to it
+    /// when indented by `indent` columns, or as lazy paragraph continuations
+    /// unless they start a sibling matching `starts_sibling` or another block.
+    fn collect_container_body(
+        &mut self,
+        first: String,
+        indent: usize,
+        starts_sibling: fn(&str) -> bool,
+    ) -> Vec<String> {
+        // Unindented lines may continue a paragraph
+        let mut lazy = !first.trim().is_empty();
+        let mut body = vec![first];
+
+        while let Some(line) = self.upcoming_lines().next() {
+            if line.trim().is_empty() {
+                // Blank lines only belong to the body if indented content follows
+                let next = self.upcoming_lines().find(|l| !l.trim().is_empty());
+                if next.is_none_or(|l| footnote::indentation(&l) < indent) {
+                    break;
                 }
-                Whitespace => {
-                    self.tokens.next();
+                body.push(String::new());
+                lazy = false;
+            } else if footnote::indentation(&line) >= indent {
+                body.push(footnote::dedent(&line, indent));
+                lazy = true;
+            } else if lazy && !starts_sibling(&line) && !self.interrupts_paragraph() {
+                body.push(line.trim_start().into());
+            } else {
+                break;
+            }
+            self.consume_line();
+        }
+
+        body
+    }
+
+    /// Parses the lines of a container body as blocks, reporting errors at
+    /// their position in the document given the body starts on `line`
+    fn parse_nested(&self, body: &[String], line: usize) -> Result<Vec<Stmt>, Vec<SyntaxError>> {
+        let body = body.join("\n");
+        let mut parser = Parser::new(&body, Lexer::new(&body)).wiki_links(self.wiki_links);
+        parser.parse().map_err(|errors| {
+            errors
+                .into_iter()
+                .map(|e| SyntaxError::new(e.msg, (e.pos.0 + line - 1, e.pos.1)))
+                .collect::<Vec<_>>()
+        })
+    }
+
+    fn parse_inlines(&self, text: &str) -> Vec<Inline> {
+        InlineParser::new(text).wiki_links(self.wiki_links).parse()
+    }
+
+    /// Returns the text of the line starting with `first_token`
+    fn peek_line(&self, first_token: &Token) -> String {
+        let rest = self.upcoming_lines().next().unwrap_or_default();
+        format!("{}{}", first_token.text, rest)
+    }
+
+    /// Iterates over the upcoming lines without consuming them
+    fn upcoming_lines(&self) -> impl Iterator<Item = String> {
+        let mut tokens = self.tokens.clone();
+        std::iter::from_fn(move || {
+            tokens.peek()?;
+            let mut line = String::new();
+            for token in tokens.by_ref() {
+                if token.kind == Newline {
+                    break;
                 }
-                _ => words.push(self.tokens.next().unwrap()), // Add word/whitespace to the paragraph
+                line.push_str(&token.text);
+            }
+            Some(line)
+        })
+    }
+
+    /// Consumes the rest of the current line and its newline
+    fn consume_line(&mut self) -> String {
+        let mut line = String::new();
+        for token in self.tokens.by_ref() {
+            if token.kind == Newline {
+                break;
             }
+            line.push_str(&token.text);
         }
+        line
+    }
 
-        Stmt::Paragraph { words }
+    /// Checks whether the upcoming tokens up to the next newline are blank
+    fn is_blank_line(&self) -> bool {
+        for token in self.tokens.clone() {
+            match token.kind {
+                Whitespace => continue,
+                Newline => return true,
+                _ => return false,
+            }
+        }
+        true
     }
 
     fn skip_blank_lines(&mut self) {
@@ -205,12 +786,23 @@ impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
         }
     }
 
+    /// Consumes blank lines, including whitespace-only ones, and returns how
+    /// many there were
+    fn consume_blank_lines(&mut self) -> usize {
+        let mut count = 0;
+        while self.tokens.peek().is_some() && self.is_blank_line() {
+            self.consume_line();
+            count += 1;
+        }
+        count
+    }
+
     fn consume_while<P>(&mut self, mut pred: P)
     where
         P: FnMut(&I::Item) -> bool,
     {
         while let Some(curr) = self.tokens.peek() {
-            if pred(&curr) {
+            if pred(curr) {
                 self.tokens.next();
             } else {
                 break;
@@ -222,6 +814,8 @@ impl<I: Iterator<Item = Token> + Clone + Debug> Parser<I> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::options::{FormatOptions, HeadingStyle};
+    use crate::syntax::inline::Inline;
     use crate::syntax::lexer::Lexer;
 
     #[test]
@@ -230,12 +824,12 @@ mod tests {
         let lexer = Lexer::new(input);
         let tokens: Vec<Token> = lexer.collect();
 
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(input, tokens.into_iter());
         let ast = parser.parse().unwrap();
         assert_eq!(
             ast,
             vec![Stmt::Paragraph {
-                words: vec![Token::new(Word, "hello".into(), (1, 1)),]
+                inlines: vec![Inline::Text("hello".into())]
             }]
         );
     }
@@ -246,12 +840,12 @@ mod tests {
         let lexer = Lexer::new(input);
         let tokens: Vec<Token> = lexer.collect();
 
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(input, tokens.into_iter());
         let ast = parser.parse().unwrap();
         assert_eq!(
             ast,
             vec![Stmt::Paragraph {
-                words: vec![Token::new(Word, "#heading".into(), (1, 1)),]
+                inlines: vec![Inline::Text("#heading".into())]
             }]
         );
     }
@@ -262,23 +856,70 @@ mod tests {
         let lexer = Lexer::new(input);
         let tokens: Vec<Token> = lexer.collect();
 
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(input, tokens.into_iter());
         let ast = parser.parse().unwrap();
         assert_eq!(
             ast,
             vec![Stmt::FrontMatter {
-                content: "var: true\n".into()
+                format: FrontMatterFormat::Yaml,
+                content: "var: true\n".into(),
+                pos: (1, 1),
             }]
         );
     }
 
+    #[test]
+    fn test_toml_and_json_front_matter() {
+        let parse = |input: &str| Parser::new(input, Lexer::new(input)).parse().unwrap();
+
+        assert_eq!(
+            parse("+++\ntitle = \"Hi\"\n+++\n\nText")[0],
+            Stmt::FrontMatter {
+                format: FrontMatterFormat::Toml,
+                content: "title = \"Hi\"\n".into(),
+                pos: (1, 1),
+            }
+        );
+        assert_eq!(
+            parse(";;;\n{ \"a\": 1 }\n;;;")[0],
+            Stmt::FrontMatter {
+                format: FrontMatterFormat::Json { fenced: true },
+                content: "{ \"a\": 1 }\n".into(),
+                pos: (1, 1),
+            }
+        );
+        let bare = parse("{\n  \"a\": [1, 2]\n}\n\nText");
+        assert_eq!(
+            bare[0],
+            Stmt::FrontMatter {
+                format: FrontMatterFormat::Json { fenced: false },
+                content: "{\n  \"a\": [1, 2]\n}".into(),
+                pos: (1, 1),
+            }
+        );
+        assert_eq!(
+            bare[0].to_markdown(&Default::default()),
+            "{\n  \"a\": [1, 2]\n}"
+        );
+    }
+
+    #[test]
+    fn test_front_matter_only_at_start() {
+        let input = "Text\n\n+++\na\n+++";
+        let ast = Parser::new(input, Lexer::new(input)).parse().unwrap();
+
+        assert!(ast
+            .iter()
+            .all(|stmt| !matches!(stmt, Stmt::FrontMatter { .. })));
+    }
+
     #[test]
     fn test_code_block_simple() {
         let input = "```\nlet x = 5;\nlet y = 10;\n```";
         let lexer = Lexer::new(input);
         let tokens: Vec<Token> = lexer.collect();
 
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(input, tokens.into_iter());
         let ast = parser.parse().unwrap();
         assert_eq!(
             ast,
@@ -295,7 +936,7 @@ mod tests {
         let lexer = Lexer::new(input);
         let tokens: Vec<Token> = lexer.collect();
 
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(input, tokens.into_iter());
         let ast = parser.parse().unwrap();
         assert_eq!(
             ast,
@@ -311,14 +952,393 @@ mod tests {
         let lexer = Lexer::new(input);
         let tokens: Vec<Token> = lexer.collect();
 
-        let mut parser = Parser::new(tokens.into_iter());
+        let mut parser = Parser::new(input, tokens.into_iter());
         if let Err(e) = parser.parse() {
             assert_eq!(
                 e,
                 vec![SyntaxError::new("Invalid code block".into(), (1, 1))]
             )
         } else {
-            assert!(false);
+            panic!("expected a syntax error");
         }
     }
+
+    #[test]
+    fn test_html_block_ends_at_blank_line() {
+        let input = "<details>\n<summary>More  info</summary>\n\nBody\n\n</details>\n";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::HtmlBlock {
+                    content: "<details>\n<summary>More  info</summary>".into()
+                },
+                Stmt::Paragraph {
+                    inlines: vec![Inline::Text("Body".into())]
+                },
+                Stmt::HtmlBlock {
+                    content: "</details>".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_html_comment_block_spans_blank_lines() {
+        let input = "<!--\n\n  keep   me\n-->\nafter";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::HtmlBlock {
+                    content: "<!--\n\n  keep   me\n-->".into()
+                },
+                Stmt::Paragraph {
+                    inlines: vec![Inline::Text("after".into())]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table() {
+        let input = "| a | b |\n|:--|--:|\n| `x|` | y \\| z |\n\nafter";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::Table {
+                    alignments: vec![Alignment::Left, Alignment::Right],
+                    header: vec![
+                        vec![Inline::Text("a".into())],
+                        vec![Inline::Text("b".into())]
+                    ],
+                    rows: vec![vec![
+                        vec![Inline::Text("`x".into())],
+                        vec![Inline::Text("`".into())],
+                    ]],
+                },
+                Stmt::Paragraph {
+                    inlines: vec![Inline::Text("after".into())]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_table_rows_match_header() {
+        let input = "| a | b |\n|---|---|\n| 1 | 2 | 3 |\n| 4 |\n";
+
+        let ast = Parser::new(input, Lexer::new(input)).parse().unwrap();
+        assert_eq!(
+            ast[0].to_markdown(&Default::default()),
+            "| a   | b   |\n| --- | --- |\n| 1   | 2   |\n| 4   |     |"
+        );
+    }
+
+    #[test]
+    fn test_table_requires_matching_delimiter_row() {
+        let input = "a | b\n--- | --- | ---\n";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        assert!(matches!(ast.as_slice(), [Stmt::Paragraph { .. }]));
+    }
+
+    #[test]
+    fn test_footnote_definition() {
+        let input = "Text[^1].\n\n[^1]: First line\ncontinued.\n\n    Second paragraph.\n\nAfter.";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::Paragraph {
+                    inlines: vec![
+                        Inline::Text("Text".into()),
+                        Inline::FootnoteRef("1".into()),
+                        Inline::Text(".".into()),
+                    ]
+                },
+                Stmt::FootnoteDefinition {
+                    label: "1".into(),
+                    content: vec![
+                        Stmt::Paragraph {
+                            inlines: vec![
+                                Inline::Text("First".into()),
+                                Inline::Space,
+                                Inline::Text("line".into()),
+                                Inline::SoftBreak,
+                                Inline::Text("continued.".into()),
+                            ]
+                        },
+                        Stmt::Paragraph {
+                            inlines: vec![
+                                Inline::Text("Second".into()),
+                                Inline::Space,
+                                Inline::Text("paragraph.".into()),
+                            ]
+                        },
+                    ]
+                },
+                Stmt::Paragraph {
+                    inlines: vec![Inline::Text("After.".into())]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_math_block() {
+        let input = "$$\n\\frac{a_1 * b}{c}\n\n  x_2\n$$\n\n$$ e^{i\\pi} $$";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::MathBlock {
+                    content: "\n\\frac{a_1 * b}{c}\n\n  x_2\n".into()
+                },
+                Stmt::MathBlock {
+                    content: " e^{i\\pi} ".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_math_block() {
+        let parse = |input: &str| Parser::new(input, Lexer::new(input)).parse().unwrap();
+
+        let ast = parse("$$x$$ is the equation.\n\nNext paragraph.\n\n# Heading");
+        assert!(matches!(ast[0], Stmt::Paragraph { .. }));
+        assert_eq!(ast.len(), 3);
+
+        let ast = parse("$$\nx = 1\n\nNext paragraph.");
+        assert!(matches!(ast[0], Stmt::Paragraph { .. }));
+        assert_eq!(ast.len(), 2);
+    }
+
+    #[test]
+    fn test_heading_keeps_escaped_closing_sequence() {
+        let format = |input: &str| {
+            Parser::new(input, Lexer::new(input)).parse().unwrap()[0]
+                .to_markdown(&Default::default())
+        };
+        assert_eq!(format("# C \\#"), "# C \\#");
+        assert_eq!(format("# \\##"), "# \\##");
+        assert_eq!(format("# C\\# and \\#1"), "# C# and #1");
+        assert_eq!(format("# C#"), "# C#");
+    }
+
+    #[test]
+    fn test_heading_closing_sequence() {
+        let format = |input: &str| {
+            Parser::new(input, Lexer::new(input)).parse().unwrap()[0]
+                .to_markdown(&Default::default())
+        };
+        assert_eq!(format("# Title #"), "# Title");
+        assert_eq!(format("## Other ##  "), "## Other");
+        assert_eq!(format("# Title {#id} ##"), "# Title {#id}");
+    }
+
+    #[test]
+    fn test_setext_heading_style() {
+        let options = FormatOptions {
+            heading_style: HeadingStyle::Setext,
+            ..Default::default()
+        };
+        let format = |input: &str| {
+            Parser::new(input, Lexer::new(input)).parse().unwrap()[0].to_markdown(&options)
+        };
+        assert_eq!(format("# Title {#top}"), "Title {#top}\n============");
+        assert_eq!(format("Hi\n="), "Hi\n===");
+        assert_eq!(format("## 文章"), "文章\n----");
+        assert_eq!(format("### Deep"), "### Deep");
+    }
+
+    #[test]
+    fn test_heading_attributes() {
+        let input = "## Getting started {  .intro #start  }";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![Stmt::Heading {
+                level: 2,
+                content: vec![
+                    Inline::Text("Getting".into()),
+                    Inline::Space,
+                    Inline::Text("started".into()),
+                ],
+                attributes: Attributes {
+                    id: Some("start".into()),
+                    classes: vec!["intro".into()],
+                    pairs: vec![],
+                },
+            }]
+        );
+        assert_eq!(
+            ast[0].to_markdown(&Default::default()),
+            "## Getting started {#start .intro}"
+        );
+    }
+
+    #[test]
+    fn test_preserves_paragraph_lines() {
+        let input = "#  Title  \t\n\n  First line  \n    second\tline\t\nthird";
+        let lexer = Lexer::new(input);
+
+        let mut parser = Parser::new(input, lexer);
+        let ast = parser.parse().unwrap();
+        let output = ast
+            .iter()
+            .map(|stmt| stmt.to_markdown(&Default::default()))
+            .collect::<Vec<_>>();
+        assert_eq!(output, ["# Title", "First line\\\nsecond line\nthird"]);
+    }
+
+    #[test]
+    fn test_ignore_directives() {
+        let source = "<!-- mdf-ignore -->\n\n|  a |\n|--|\n\ntext\n\n\
+            <!-- mdf-ignore-start -->\n  x  \n\n  y\n<!-- mdf-ignore-end -->\n\
+            <!-- mdf-ignore -->\n```\n\n```\nafter";
+        let lexer = Lexer::new(source);
+
+        let mut parser = Parser::new(source, lexer);
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast[0],
+            Stmt::Verbatim {
+                content: "<!-- mdf-ignore -->\n\n|  a |\n|--|".into()
+            }
+        );
+        assert_eq!(
+            ast[2],
+            Stmt::Verbatim {
+                content: "<!-- mdf-ignore-start -->\n  x  \n\n  y\n<!-- mdf-ignore-end -->".into()
+            }
+        );
+        assert_eq!(
+            ast[3],
+            Stmt::Verbatim {
+                content: "<!-- mdf-ignore -->\n```\n\n```".into()
+            }
+        );
+        assert_eq!(ast.len(), 5);
+    }
+
+    #[test]
+    fn test_ignored_region_is_copied_exactly() {
+        let source =
+            "<!-- mdf-ignore-start -->\r\n│文です│\t x\r\n\t😀  \n<!-- mdf-ignore-end -->\nafter";
+        let mut parser = Parser::new(source, Lexer::new(source));
+        let ast = parser.parse().unwrap();
+        assert_eq!(
+            ast[0],
+            Stmt::Verbatim {
+                content: source[..source.find("\nafter").unwrap()].into()
+            }
+        );
+        assert_eq!(ast.len(), 2);
+    }
+
+    #[test]
+    fn test_ignored_block_is_parsed_to_its_end() {
+        let source = "<!-- mdf-ignore -->\n$$\na  = b\n\nc  = d\n$$\n\n\
+            <!-- mdf-ignore -->\n    code\n\n    more   code\n\nafter";
+        let ast = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        assert_eq!(
+            ast,
+            vec![
+                Stmt::Verbatim {
+                    content: "<!-- mdf-ignore -->\n$$\na  = b\n\nc  = d\n$$".into()
+                },
+                Stmt::Verbatim {
+                    content: "<!-- mdf-ignore -->\n    code\n\n    more   code".into()
+                },
+                Stmt::Paragraph {
+                    inlines: vec![Inline::Text("after".into())]
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paragraph_ends_at_block_start() {
+        let kinds = |input: &str| {
+            Parser::new(input, Lexer::new(input))
+                .parse()
+                .unwrap()
+                .iter()
+                .map(|stmt| match stmt {
+                    Stmt::Paragraph { .. } => "paragraph",
+                    Stmt::List { .. } => "list",
+                    Stmt::BlockQuote { .. } => "quote",
+                    Stmt::CodeBlock { .. } => "code",
+                    Stmt::Heading { .. } => "heading",
+                    Stmt::ThematicBreak { .. } => "break",
+                    Stmt::Table { .. } => "table",
+                    Stmt::HtmlBlock { .. } => "html",
+                    _ => "other",
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(kinds("Text\n- item"), ["paragraph", "list"]);
+        assert_eq!(kinds("Text\n1. item"), ["paragraph", "list"]);
+        assert_eq!(kinds("Text\n2. item\n-\n"), ["heading"]);
+        assert_eq!(kinds("Text\n> quote"), ["paragraph", "quote"]);
+        assert_eq!(kinds("Text\n```\ncode\n```"), ["paragraph", "code"]);
+        assert_eq!(kinds("Text\n## Heading"), ["paragraph", "heading"]);
+        assert_eq!(kinds("Text\n* * *"), ["paragraph", "break"]);
+        assert_eq!(kinds("Text\n| a |\n|---|"), ["paragraph", "table"]);
+        assert_eq!(kinds("Text\n<div>\n</div>"), ["paragraph", "html"]);
+        assert_eq!(kinds("Text\n<span>x</span>"), ["paragraph"]);
+        assert_eq!(kinds("Text\n#hashtag"), ["paragraph"]);
+    }
+
+    #[test]
+    fn test_setext_heading() {
+        let input = "Title {#top}\n===\n\nSub\ntitle\n---\n\n---";
+        let ast = Parser::new(input, Lexer::new(input)).parse().unwrap();
+        let output = ast
+            .iter()
+            .map(|stmt| stmt.to_markdown(&Default::default()))
+            .collect::<Vec<_>>();
+        assert_eq!(output, ["# Title {#top}", "## Sub title", "---"]);
+    }
+
+    #[test]
+    fn test_indented_code_block() {
+        let source = "Text\n    continued\n\n    code\n\n\tmore  code\n\nafter";
+        let ast = Parser::new(source, Lexer::new(source)).parse().unwrap();
+        assert_eq!(
+            ast[1],
+            Stmt::IndentedCodeBlock {
+                content: "code\n\nmore  code".into()
+            }
+        );
+        assert_eq!(
+            ast[1].to_markdown(&Default::default()),
+            "    code\n\n    more  code"
+        );
+        assert_eq!(ast.len(), 3);
+    }
 }
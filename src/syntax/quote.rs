@@ -0,0 +1,86 @@
+use super::stmt::Stmt;
+use crate::options::FormatOptions;
+
+/// Prefix of every non-blank block quote line
+const MARKER: &str = "> ";
+
+/// Returns the text after the `>` marker of a block quote line and the
+/// optional space following it
+pub fn parse_quote_line(line: &str) -> Option<String> {
+    let text = line.trim_start_matches(' ');
+    if line.len() - text.len() > 3 {
+        return None;
+    }
+
+    let rest = text.strip_prefix('>')?;
+    Some(rest.strip_prefix([' ', '\t']).unwrap_or(rest).into())
+}
+
+/// Converts a block quote into Markdown, prefixing its lines with `> ` and
+/// blank lines with a lone `>`
+pub fn to_markdown(content: &[Stmt], options: &FormatOptions) -> String {
+    let options = &FormatOptions {
+        line_width: options.line_width.saturating_sub(MARKER.len()),
+        ..options.clone()
+    };
+    let body = content
+        .iter()
+        .map(|stmt| stmt.to_markdown(options))
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if body.is_empty() {
+        return MARKER.trim_end().into();
+    }
+
+    body.lines()
+        .map(|line| {
+            if line.is_empty() {
+                MARKER.trim_end().into()
+            } else {
+                format!("{}{}", MARKER, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::ProseWrap;
+    use crate::syntax::format;
+
+    #[test]
+    fn test_parse_quote_line() {
+        assert_eq!(parse_quote_line("> text"), Some("text".into()));
+        assert_eq!(parse_quote_line("  >>  nested"), Some(">  nested".into()));
+        assert_eq!(parse_quote_line(">"), Some("".into()));
+        assert_eq!(parse_quote_line("    > code"), None);
+        assert_eq!(parse_quote_line("a > b"), None);
+    }
+
+    #[test]
+    fn test_to_markdown() {
+        let input = ">quote here\nlazy line\n>\n> > nested\n> - item\n\nafter";
+
+        assert_eq!(
+            format(input, &FormatOptions::default()),
+            "> quote here\n> lazy line\n>\n> > nested\n>\n> - item\n\nafter"
+        );
+    }
+
+    #[test]
+    fn test_reflow_quote() {
+        let options = FormatOptions {
+            prose_wrap: ProseWrap::Always,
+            line_width: 12,
+            ..Default::default()
+        };
+        let input = "> quote here\n> more words follow";
+
+        assert_eq!(
+            format(input, &options),
+            "> quote here\n> more words\n> follow"
+        );
+    }
+}
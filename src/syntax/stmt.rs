@@ -1,43 +1,210 @@
-use super::tokens::*;
+use super::attributes::Attributes;
+use super::definition::{self, DefinitionItem};
+use super::footnote;
+use super::front_matter::{self, FrontMatterFormat};
+use super::inline::{self, Inline};
+use super::list::{self, ListItem};
+use super::quote;
+use super::table::{self, Alignment};
+use crate::options::{FormatOptions, HeadingStyle, IndentStyle};
+use crate::width;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum Stmt {
     // Toc,
     Heading {
         level: usize,
-        content: String,
+        content: Vec<Inline>,
+        /// Trailing `{#id .class key=value}` block
+        attributes: Attributes,
     },
     Paragraph {
-        words: Vec<Token>,
+        inlines: Vec<Inline>,
     },
     FrontMatter {
+        format: FrontMatterFormat,
         content: String,
+        /// Position of the opening delimiter, for diagnostics
+        pos: (usize, usize),
     },
     CodeBlock {
         language: Option<String>,
         content: String,
     },
+    /// A code block indented by four columns, without them
+    IndentedCodeBlock {
+        content: String,
+    },
+    HtmlBlock {
+        content: String,
+    },
+    MathBlock {
+        content: String,
+    },
+    Table {
+        alignments: Vec<Alignment>,
+        header: Vec<Vec<Inline>>,
+        rows: Vec<Vec<Vec<Inline>>>,
+    },
+    FootnoteDefinition {
+        label: String,
+        content: Vec<Stmt>,
+    },
+    DefinitionList {
+        items: Vec<DefinitionItem>,
+    },
+    List {
+        items: Vec<ListItem>,
+        /// Whether blank lines separate the items or the blocks inside them
+        loose: bool,
+    },
+    BlockQuote {
+        content: Vec<Stmt>,
+    },
+    /// A thematic break, kept as written
+    ThematicBreak {
+        content: String,
+    },
+    /// A region excluded by an ignore directive, kept exactly as written
+    Verbatim {
+        content: String,
+    },
 }
 
 impl Stmt {
     /// Converts a `Stmt` into a Markdown string.
-    pub fn to_markdown(&self) -> String {
+    pub fn to_markdown(&self, options: &FormatOptions) -> String {
         match self {
-            Stmt::Heading { level, content } => {
-                format!("{} {}", "#".repeat(*level), content)
-            }
-            Stmt::Paragraph { words } => words
-                .iter()
-                .map(|token| token.text.as_str())
-                .collect::<Vec<_>>()
-                .join(" "),
-            Stmt::FrontMatter { content } => {
-                format!("---\n{}---", content)
+            Stmt::Heading {
+                level,
+                content,
+                attributes,
+            } => {
+                let mut text = inline::cell_to_markdown(content, options);
+                // A trailing run of `#` would read as a closing sequence
+                let run = text.trim_end_matches('#');
+                if run.len() < text.len() && (run.is_empty() || run.ends_with(' ')) {
+                    text.insert(run.len(), '\\');
+                }
+                if !attributes.is_empty() {
+                    if !content.is_empty() {
+                        text.push(' ');
+                    }
+                    text.push_str(&attributes.to_markdown());
+                }
+                match (options.heading_style, level) {
+                    (HeadingStyle::Setext, 1 | 2) if !content.is_empty() => {
+                        let underline = if *level == 1 { "=" } else { "-" };
+                        let len = width::display_width(&text).max(3);
+                        format!("{}\n{}", text, underline.repeat(len))
+                    }
+                    _ => format!("{} {}", "#".repeat(*level), text),
+                }
             }
+            Stmt::Paragraph { inlines } => inline::to_markdown(inlines, options),
+            Stmt::FrontMatter {
+                format, content, ..
+            } => front_matter::to_markdown(*format, content),
             Stmt::CodeBlock { language, content } => {
                 let lang_str = language.as_ref().map(|l| l.as_str()).unwrap_or("");
                 format!("```{}\n{}```", lang_str, content)
             }
+            Stmt::IndentedCodeBlock { content } => content
+                .split('\n')
+                .map(|line| match line {
+                    "" => String::new(),
+                    line => format!("    {}", line),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            Stmt::HtmlBlock { content } => content.clone(),
+            Stmt::Verbatim { content } => content.clone(),
+            Stmt::MathBlock { content } => format!("$${}$$", content),
+            Stmt::Table {
+                alignments,
+                header,
+                rows,
+            } => table::to_markdown(alignments, header, rows, options),
+            Stmt::FootnoteDefinition { label, content } => {
+                footnote::to_markdown(label, content, options)
+            }
+            Stmt::DefinitionList { items } => definition::to_markdown(items, options),
+            Stmt::List { items, loose } => list::to_markdown(items, *loose, options),
+            Stmt::BlockQuote { content } => quote::to_markdown(content, options),
+            Stmt::ThematicBreak { content } => content.clone(),
+        }
+    }
+
+    /// Calls `f` on every `Inline` in this `Stmt`, in document order.
+    pub fn visit_inlines_mut<F: FnMut(&mut Inline)>(&mut self, f: &mut F) {
+        match self {
+            Stmt::Heading { content, .. } | Stmt::Paragraph { inlines: content } => {
+                content.iter_mut().for_each(|i| i.visit_mut(f))
+            }
+            Stmt::Table { header, rows, .. } => std::iter::once(header)
+                .chain(rows.iter_mut())
+                .flatten()
+                .flatten()
+                .for_each(|i| i.visit_mut(f)),
+            Stmt::FootnoteDefinition { content, .. } | Stmt::BlockQuote { content } => {
+                content.iter_mut().for_each(|s| s.visit_inlines_mut(f))
+            }
+            Stmt::List { items, .. } => items
+                .iter_mut()
+                .flat_map(|item| item.content.iter_mut())
+                .for_each(|s| s.visit_inlines_mut(f)),
+            Stmt::DefinitionList { items } => {
+                for item in items {
+                    item.term.iter_mut().for_each(|i| i.visit_mut(f));
+                    item.definitions
+                        .iter_mut()
+                        .flatten()
+                        .for_each(|s| s.visit_inlines_mut(f));
+                }
+            }
+            Stmt::FrontMatter { .. }
+            | Stmt::CodeBlock { .. }
+            | Stmt::IndentedCodeBlock { .. }
+            | Stmt::HtmlBlock { .. }
+            | Stmt::MathBlock { .. }
+            | Stmt::ThematicBreak { .. }
+            | Stmt::Verbatim { .. } => {}
         }
     }
 }
+
+/// Returns the indent of footnote and definition bodies: a tab, or as many
+/// spaces as the indent width but at least the four columns that keep them
+/// part of the body
+pub fn body_indent(options: &FormatOptions) -> String {
+    match options.indent_style {
+        IndentStyle::Tab => "\t".into(),
+        IndentStyle::Space => " ".repeat(options.indent_width.max(4)),
+    }
+}
+
+/// Converts nested blocks into Markdown joined by `separator`, indenting
+/// every non-blank line by `indent` and narrowing the line width to match
+pub fn indented(stmts: &[Stmt], indent: &str, separator: &str, options: &FormatOptions) -> String {
+    let options = &FormatOptions {
+        line_width: options
+            .line_width
+            .saturating_sub(footnote::indentation(indent)),
+        ..options.clone()
+    };
+    stmts
+        .iter()
+        .map(|stmt| stmt.to_markdown(options))
+        .collect::<Vec<_>>()
+        .join(separator)
+        .lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{}{}", indent, line)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,272 @@
+use super::inline::{self, Inline};
+use crate::options::{FormatOptions, TableStyle};
+use crate::width::display_width;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+impl Alignment {
+    /// Writes the delimiter row cell for a column of `width`
+    fn delimiter(&self, width: usize) -> String {
+        match self {
+            Alignment::None => "-".repeat(width),
+            Alignment::Left => format!(":{}", "-".repeat(width - 1)),
+            Alignment::Center => format!(":{}:", "-".repeat(width - 2)),
+            Alignment::Right => format!("{}:", "-".repeat(width - 1)),
+        }
+    }
+
+    fn pad(&self, text: &str, width: usize) -> String {
+        let fill = width.saturating_sub(display_width(text));
+        let (left, right) = match self {
+            Alignment::None | Alignment::Left => (0, fill),
+            Alignment::Center => (fill / 2, fill - fill / 2),
+            Alignment::Right => (fill, 0),
+        };
+        format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+    }
+}
+
+/// Splits a table row into its trimmed cells on unescaped pipes
+pub fn split_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+
+    let mut cells = vec![];
+    let mut cell = String::new();
+    let mut escaped = false;
+    for ch in line.chars() {
+        if ch == '|' && !escaped {
+            cells.push(cell.trim().to_string());
+            cell.clear();
+        } else {
+            cell.push(ch);
+        }
+        escaped = ch == '\\' && !escaped;
+    }
+    // A trailing pipe closes the last cell instead of opening a new one
+    if !cell.trim().is_empty() || cells.is_empty() {
+        cells.push(cell.trim().to_string());
+    }
+    cells
+}
+
+/// Parses a delimiter row like `| :--- | :-: | --: |` into column alignments
+pub fn parse_delimiter_row(line: &str) -> Option<Vec<Alignment>> {
+    if !line.contains('|') {
+        return None;
+    }
+
+    split_cells(line)
+        .iter()
+        .map(|cell| {
+            let left = cell.starts_with(':');
+            let right = cell.ends_with(':');
+            let dashes = cell.trim_start_matches(':').trim_end_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (left, right) {
+                (false, false) => Alignment::None,
+                (true, false) => Alignment::Left,
+                (true, true) => Alignment::Center,
+                (false, true) => Alignment::Right,
+            })
+        })
+        .collect()
+}
+
+/// Converts a table into Markdown in the configured `TableStyle`.
+pub fn to_markdown(
+    alignments: &[Alignment],
+    header: &[Vec<Inline>],
+    rows: &[Vec<Vec<Inline>>],
+    options: &FormatOptions,
+) -> String {
+    let render_row = |row: &[Vec<Inline>]| {
+        row.iter()
+            .map(|cell| inline::cell_to_markdown(cell, options))
+            .collect::<Vec<_>>()
+    };
+    let header = render_row(header);
+    let mut rows = rows.iter().map(|row| render_row(row)).collect::<Vec<_>>();
+    for row in rows.iter_mut() {
+        row.resize(header.len(), String::new());
+    }
+
+    let columns = header.len();
+    let aligned = || {
+        let widths = (0..columns)
+            .map(|col| {
+                std::iter::once(&header)
+                    .chain(&rows)
+                    .filter_map(|row| row.get(col))
+                    .map(|cell| display_width(cell))
+                    .fold(3, usize::max)
+            })
+            .collect::<Vec<_>>();
+        layout(alignments, &header, &rows, &widths)
+    };
+    let compact = || layout(alignments, &header, &rows, &vec![0; columns]);
+
+    match options.table_style {
+        TableStyle::Aligned => aligned(),
+        TableStyle::Compact => compact(),
+        TableStyle::Auto => {
+            let table = aligned();
+            if table
+                .lines()
+                .any(|line| display_width(line) > options.table_max_width)
+            {
+                compact()
+            } else {
+                table
+            }
+        }
+    }
+}
+
+/// Lays out rendered cells padded to `widths`; a zero width leaves cells unpadded
+fn layout(
+    alignments: &[Alignment],
+    header: &[String],
+    rows: &[Vec<String>],
+    widths: &[usize],
+) -> String {
+    let alignment = |col: usize| alignments.get(col).copied().unwrap_or(Alignment::None);
+    let format_row = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .enumerate()
+            .map(|(col, cell)| alignment(col).pad(cell, widths[col]))
+            .collect::<Vec<_>>();
+        format!("| {} |", cells.join(" | "))
+    };
+
+    let delimiters = alignments
+        .iter()
+        .enumerate()
+        .map(|(col, alignment)| alignment.delimiter(widths[col].max(3)))
+        .collect::<Vec<_>>();
+
+    let mut lines = vec![format_row(header), format_row(&delimiters)];
+    lines.extend(rows.iter().map(|row| format_row(row)));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::syntax::inline::InlineParser;
+
+    fn row(cells: &[&str]) -> Vec<Vec<Inline>> {
+        cells
+            .iter()
+            .map(|cell| InlineParser::new(cell).parse())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_cells() {
+        assert_eq!(split_cells("| a | b \\| c |"), vec!["a", "b \\| c"]);
+        assert_eq!(split_cells("a|b"), vec!["a", "b"]);
+        assert_eq!(split_cells("| a | |"), vec!["a", ""]);
+    }
+
+    #[test]
+    fn test_parse_delimiter_row() {
+        assert_eq!(
+            parse_delimiter_row("|:---|:-:| ---: | - |"),
+            Some(vec![
+                Alignment::Left,
+                Alignment::Center,
+                Alignment::Right,
+                Alignment::None,
+            ])
+        );
+        assert_eq!(parse_delimiter_row("---"), None);
+        assert_eq!(parse_delimiter_row("| -- | x |"), None);
+    }
+
+    #[test]
+    fn test_to_markdown_aligned() {
+        let alignments = [Alignment::Left, Alignment::Center, Alignment::Right];
+        let header = row(&["Name", "Kind", "Size"]);
+        let rows = vec![row(&["a", "file", "10"]), row(&["longer name", "dir"])];
+
+        assert_eq!(
+            to_markdown(&alignments, &header, &rows, &FormatOptions::default()),
+            "\
+| Name        | Kind | Size |
+| :---------- | :--: | ---: |
+| a           | file |   10 |
+| longer name | dir  |      |"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_wide_characters() {
+        let alignments = [Alignment::None, Alignment::Right];
+        let header = row(&["名前", "説明"]);
+        let rows = vec![row(&["a", "🚀"]), row(&["コード", "e\u{301}"])];
+
+        assert_eq!(
+            to_markdown(&alignments, &header, &rows, &FormatOptions::default()),
+            "\
+| 名前   | 説明 |
+| ------ | ---: |
+| a      |   🚀 |
+| コード |    e\u{301} |"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_compact() {
+        let options = FormatOptions {
+            table_style: TableStyle::Compact,
+            ..Default::default()
+        };
+        let alignments = [Alignment::Left, Alignment::Center, Alignment::None];
+        let header = row(&["Version", "Date", "Notes"]);
+        let rows = vec![row(&["1.0.0", "2024-01-01", "First release"])];
+
+        assert_eq!(
+            to_markdown(&alignments, &header, &rows, &options),
+            "\
+| Version | Date | Notes |
+| :-- | :-: | --- |
+| 1.0.0 | 2024-01-01 | First release |"
+        );
+    }
+
+    #[test]
+    fn test_to_markdown_auto_falls_back_to_compact() {
+        let alignments = [Alignment::None, Alignment::None];
+        let header = row(&["a", "b"]);
+        let rows = vec![row(&["short", "a much longer cell"])];
+
+        let narrow = FormatOptions {
+            table_style: TableStyle::Auto,
+            table_max_width: 20,
+            ..Default::default()
+        };
+        assert_eq!(
+            to_markdown(&alignments, &header, &rows, &narrow),
+            "| a | b |\n| --- | --- |\n| short | a much longer cell |"
+        );
+
+        let wide = FormatOptions {
+            table_max_width: 40,
+            ..narrow
+        };
+        assert_eq!(
+            to_markdown(&alignments, &header, &rows, &wide),
+            to_markdown(&alignments, &header, &rows, &FormatOptions::default())
+        );
+    }
+}
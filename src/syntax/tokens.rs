@@ -20,11 +20,18 @@ pub struct Token {
     pub kind: TokenKind,
     pub text: String,
     pub pos: (usize, usize),
+    /// Byte offset of the text in the source
+    pub offset: usize,
 }
 
 impl Token {
-    pub fn new(kind: TokenKind, text: String, pos: (usize, usize)) -> Self {
-        Self { kind, text, pos }
+    pub fn new(kind: TokenKind, text: String, pos: (usize, usize), offset: usize) -> Self {
+        Self {
+            kind,
+            text,
+            pos,
+            offset,
+        }
     }
 }
 
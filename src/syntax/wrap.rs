@@ -0,0 +1,235 @@
+use super::html;
+use super::inline::Fragment;
+use crate::options::{FormatOptions, ProseWrap};
+use crate::width::display_width;
+
+/// Splits inline fragments into output lines. Hard breaks always end a line;
+/// soft breaks are kept, joined into spaces, or replaced by breaks that fill
+/// lines up to the line width or end sentences, depending on the prose wrap
+/// mode. Every line but the last ends with the break fragment that ends it.
+pub fn layout(fragments: Vec<Fragment>, options: &FormatOptions) -> Vec<Vec<Fragment>> {
+    match options.prose_wrap {
+        ProseWrap::Preserve => split_after_breaks(fragments),
+        ProseWrap::Never => split_after_breaks(
+            fragments
+                .into_iter()
+                .map(|f| match f {
+                    Fragment::SoftBreak => Fragment::Space,
+                    f => f,
+                })
+                .collect(),
+        ),
+        ProseWrap::Always => fill(fragments, options.line_width, None),
+        ProseWrap::Sentence => fill(fragments, usize::MAX, Some(&options.abbreviations)),
+    }
+}
+
+fn split_after_breaks(fragments: Vec<Fragment>) -> Vec<Vec<Fragment>> {
+    let mut lines = vec![vec![]];
+    for fragment in fragments {
+        let ends_line = matches!(fragment, Fragment::HardBreak | Fragment::SoftBreak);
+        lines.last_mut().unwrap().push(fragment);
+        if ends_line {
+            lines.push(vec![]);
+        }
+    }
+    lines
+}
+
+/// Greedily fills lines with the words between spaces and soft breaks. With
+/// `abbreviations`, every sentence also starts a new line.
+fn fill(
+    fragments: Vec<Fragment>,
+    width: usize,
+    abbreviations: Option<&[String]>,
+) -> Vec<Vec<Fragment>> {
+    let mut filler = Filler {
+        width,
+        abbreviations,
+        lines: vec![],
+        line: vec![],
+        line_width: 0,
+        sentence_ended: false,
+        link_depth: 0,
+    };
+    let mut word = vec![];
+
+    for fragment in fragments {
+        match fragment {
+            Fragment::Space | Fragment::SoftBreak => filler.place(&mut word),
+            Fragment::HardBreak => {
+                filler.place(&mut word);
+                filler.end_line(Fragment::HardBreak);
+            }
+            fragment => word.push(fragment),
+        }
+    }
+    filler.place(&mut word);
+    filler.lines.push(filler.line);
+    filler.lines
+}
+
+struct Filler<'a> {
+    width: usize,
+    abbreviations: Option<&'a [String]>,
+    lines: Vec<Vec<Fragment>>,
+    line: Vec<Fragment>,
+    line_width: usize,
+    /// Whether the last word placed could end a sentence
+    sentence_ended: bool,
+    /// How many links the next word is inside of, as sentences never break
+    /// inside link text
+    link_depth: usize,
+}
+
+impl Filler<'_> {
+    /// Appends `word` to the current line, or to a new one if it would not fit
+    fn place(&mut self, word: &mut Vec<Fragment>) {
+        if word.is_empty() {
+            return;
+        }
+        let word_width = display_width(&word.iter().map(Fragment::to_markdown).collect::<String>());
+        let new_sentence = self.sentence_ended && self.link_depth == 0 && starts_sentence(word);
+        if let Some(abbreviations) = self.abbreviations {
+            self.sentence_ended = ends_sentence(word, abbreviations);
+        }
+        for fragment in word.iter() {
+            match fragment {
+                Fragment::Raw(raw) if raw == "[" || raw == "![" => self.link_depth += 1,
+                Fragment::Raw(raw) if raw.starts_with("](") => {
+                    self.link_depth = self.link_depth.saturating_sub(1)
+                }
+                _ => {}
+            }
+        }
+        if !self.line.is_empty() {
+            let full = self.line_width + 1 + word_width > self.width;
+            if (full || new_sentence) && can_start_line(word) {
+                self.end_line(Fragment::SoftBreak);
+            } else {
+                self.line.push(Fragment::Space);
+                self.line_width += 1;
+            }
+        }
+        self.line.append(word);
+        self.line_width += word_width;
+    }
+
+    fn end_line(&mut self, end: Fragment) {
+        self.line.push(end);
+        self.lines.push(std::mem::take(&mut self.line));
+        self.line_width = 0;
+    }
+}
+
+const OPENERS: [char; 8] = ['"', '\'', '(', '[', '*', '_', '\u{201C}', '\u{2018}'];
+const CLOSERS: [char; 8] = ['"', '\'', ')', ']', '*', '_', '\u{201D}', '\u{2019}'];
+
+/// Checks whether a word ends with sentence punctuation and is not a listed
+/// abbreviation or an initial. Punctuation inside code, links or other markup
+/// does not count, and neither do the dots inside version numbers, since
+/// those never end a word.
+fn ends_sentence(word: &[Fragment], abbreviations: &[String]) -> bool {
+    let Some(Fragment::Text(last)) = word.last() else {
+        return false;
+    };
+    let last = last.trim_end_matches(CLOSERS);
+    if !last.ends_with(['.', '!', '?']) {
+        return false;
+    }
+    if !last.ends_with('.') {
+        return true;
+    }
+
+    let text = word.iter().map(Fragment::to_markdown).collect::<String>();
+    let text = text.trim_start_matches(OPENERS).trim_end_matches(CLOSERS);
+    let initial = text.chars().count() == 2 && text.starts_with(char::is_alphabetic);
+    !initial && !abbreviations.iter().any(|a| a.eq_ignore_ascii_case(text))
+}
+
+/// Sentences start with anything but a lowercase letter
+fn starts_sentence(word: &[Fragment]) -> bool {
+    let text = word.iter().map(Fragment::to_markdown).collect::<String>();
+    text.trim_start_matches(OPENERS)
+        .chars()
+        .next()
+        .is_some_and(|c| !c.is_lowercase())
+}
+
+/// Moving raw HTML or display math to the start of a line would turn it
+/// into a block, so such words stay where they are
+fn can_start_line(word: &[Fragment]) -> bool {
+    match word.first() {
+        Some(Fragment::Raw(raw)) => html::block_start(raw).is_none() && !raw.starts_with("$$"),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::FormatOptions;
+    use crate::syntax::format;
+
+    fn options(prose_wrap: ProseWrap, line_width: usize) -> FormatOptions {
+        FormatOptions {
+            prose_wrap,
+            line_width,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_never_joins_lines() {
+        assert_eq!(
+            format("one\ntwo  \nthree\nfour", &options(ProseWrap::Never, 80)),
+            "one two\\\nthree four"
+        );
+    }
+
+    #[test]
+    fn test_preserve_keeps_lines() {
+        assert_eq!(
+            format("one\ntwo three\nfour", &options(ProseWrap::Preserve, 5)),
+            "one\ntwo three\nfour"
+        );
+    }
+
+    #[test]
+    fn test_always_fills_to_width() {
+        let input =
+            "The `quick brown` fox\njumps over [the lazy](https://example.com/dog) dog 1. yes";
+
+        assert_eq!(
+            format(input, &options(ProseWrap::Always, 20)),
+            "The `quick brown`\nfox jumps over [the\nlazy](https://example.com/dog)\ndog 1. yes"
+        );
+        assert_eq!(
+            format("a b\\\nc d", &options(ProseWrap::Always, 1)),
+            "a\nb\\\nc\nd"
+        );
+    }
+
+    #[test]
+    fn test_sentence_per_line() {
+        let input = "Hi Dr. Who, see e.g. `a. B` and [docs. Here](x). \
+            Version 1.2.3 is out! J. R. R. Tolkien wrote it. is it? \"Yes.\" Fine.";
+
+        assert_eq!(
+            format(input, &options(ProseWrap::Sentence, 10)),
+            "Hi Dr. Who, see e.g. `a. B` and [docs. Here](x).\n\
+            Version 1.2.3 is out!\n\
+            J. R. R. Tolkien wrote it. is it?\n\
+            \"Yes.\"\n\
+            Fine."
+        );
+    }
+
+    #[test]
+    fn test_always_keeps_html_blocks_inline() {
+        assert_eq!(
+            format("aaaa <div> b", &options(ProseWrap::Always, 6)),
+            "aaaa <div>\nb"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+/// Ranges of characters that take no columns: combining marks, joiners,
+/// variation selectors and emoji modifiers
+const ZERO_WIDTH: &[(u32, u32)] = &[
+    (0x0300, 0x036F),
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x05BF, 0x05BF),
+    (0x05C1, 0x05C2),
+    (0x05C4, 0x05C5),
+    (0x05C7, 0x05C7),
+    (0x0610, 0x061A),
+    (0x064B, 0x065F),
+    (0x0670, 0x0670),
+    (0x06D6, 0x06DC),
+    (0x06DF, 0x06E4),
+    (0x06E7, 0x06E8),
+    (0x06EA, 0x06ED),
+    (0x0900, 0x0902),
+    (0x093A, 0x093A),
+    (0x093C, 0x093C),
+    (0x0941, 0x0948),
+    (0x094D, 0x094D),
+    (0x0951, 0x0957),
+    (0x0962, 0x0963),
+    (0x0E31, 0x0E31),
+    (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E),
+    (0x1160, 0x11FF),
+    (0x1AB0, 0x1AFF),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200F),
+    (0x2028, 0x202E),
+    (0x2060, 0x2064),
+    (0x20D0, 0x20FF),
+    (0x302A, 0x302D),
+    (0x3099, 0x309A),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+    (0xFEFF, 0xFEFF),
+    (0x1F3FB, 0x1F3FF),
+    (0xE0000, 0xE007F),
+    (0xE0100, 0xE01EF),
+];
+
+/// Ranges of East Asian wide and fullwidth characters and emoji presentation
+/// characters, which take two columns
+const WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115F),
+    (0x231A, 0x231B),
+    (0x2329, 0x232A),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x2E80, 0x3029),
+    (0x302E, 0x303E),
+    (0x3041, 0x3098),
+    (0x309B, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x16FE0, 0x16FE4),
+    (0x17000, 0x18AFF),
+    (0x1B000, 0x1B2FF),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F200, 0x1F202),
+    (0x1F210, 0x1F23B),
+    (0x1F240, 0x1F248),
+    (0x1F250, 0x1F251),
+    (0x1F260, 0x1F265),
+    (0x1F300, 0x1F3FA),
+    (0x1F400, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F7E0, 0x1F7EB),
+    (0x1F7F0, 0x1F7F0),
+    (0x1F90C, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+const EMOJI_PRESENTATION: char = '\u{FE0F}';
+
+fn in_ranges(ranges: &[(u32, u32)], ch: char) -> bool {
+    let code = ch as u32;
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < code {
+                std::cmp::Ordering::Less
+            } else if start > code {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// Returns the number of terminal columns a single character takes
+pub fn char_width(ch: char) -> usize {
+    if ch.is_control() || in_ranges(ZERO_WIDTH, ch) {
+        0
+    } else if in_ranges(WIDE, ch) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Returns the number of terminal columns `text` takes when displayed
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut prev = None;
+    for ch in text.chars() {
+        width += match (prev, ch) {
+            // Joined emoji sequences render as their first emoji
+            (Some(ZERO_WIDTH_JOINER), _) => 0,
+            // A text-style symbol followed by VS16 renders as a wide emoji
+            (Some(p), EMOJI_PRESENTATION) if char_width(p) == 1 => 1,
+            _ => char_width(ch),
+        };
+        prev = Some(ch);
+    }
+    width
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ascii() {
+        assert_eq!(display_width("hello"), 5);
+        assert_eq!(display_width(""), 0);
+    }
+
+    #[test]
+    fn test_east_asian_wide() {
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("中文abc"), 7);
+        assert_eq!(display_width("ｶﾀｶﾅ"), 4);
+        assert_eq!(display_width("한국어"), 6);
+    }
+
+    #[test]
+    fn test_combining_marks() {
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("Ame\u{301}lie"), 6);
+    }
+
+    #[test]
+    fn test_emoji() {
+        assert_eq!(display_width("🚀"), 2);
+        assert_eq!(display_width("👍🏽"), 2);
+        assert_eq!(display_width("👨\u{200D}👩\u{200D}👧"), 2);
+        assert_eq!(display_width("❤\u{FE0F}"), 2);
+        assert_eq!(display_width("🇯🇵"), 2);
+    }
+}